@@ -0,0 +1,79 @@
+use std::{env::args, fs::File, io::BufWriter};
+
+use onitama_solver::{
+    card::parse_card_set,
+    onitama_simd::{base_card_sets, parse_size, Atlas, Threads},
+};
+
+const USAGE: &str =
+    "expected args: [--dtc] [--out file.csv] [--skip n] [--take n] [--threads n, 0 for all cores] num pieces [card sets, like ox,boar,horse,elephant,crab]";
+
+pub fn main() {
+    let mut dtc = false;
+    let mut output = None;
+    let mut skip = 0;
+    let mut take = usize::MAX;
+    let mut threads = Threads::Count(1);
+    let mut positional = vec![];
+    let mut iter = args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut number = || iter.next().expect(USAGE).parse().expect("expected integer");
+        match arg.as_str() {
+            "--dtc" => dtc = true,
+            "--out" => output = Some(iter.next().expect(USAGE)),
+            "--skip" => skip = number(),
+            "--take" => take = number(),
+            "--threads" => {
+                threads = match number() {
+                    0 => Threads::Global,
+                    n => Threads::Count(n),
+                }
+            }
+            flag if flag.starts_with("--") => panic!("unknown flag {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+
+    let size = positional.first().expect(USAGE);
+    let size = parse_size(size).unwrap_or_else(|err| panic!("{err}"));
+
+    // all sets of the base game if none are given
+    let sets: Vec<u64> = match &positional[1..] {
+        [] => base_card_sets().collect(),
+        sets => sets
+            .iter()
+            .map(|set| {
+                let names: Vec<&str> = set.split(',').collect();
+                parse_card_set(&names).unwrap_or_else(|err| panic!("{err}"))
+            })
+            .collect(),
+    };
+    let sets: Vec<u64> = sets.into_iter().skip(skip).take(take).collect();
+
+    let mut atlas = Atlas::new(size).dtc(dtc).threads(threads);
+    for (i, &cards) in sets.iter().enumerate() {
+        let entry = atlas.add(cards);
+        let how = match entry.mirror_of {
+            Some(_) => "copied from its mirror".to_string(),
+            None => format!("built in {:.3} seconds", entry.elapsed.as_secs_f32()),
+        };
+        println!(
+            "{}/{}: {cards:#b} has {} wins and {:.4} draw rate, {how}",
+            i + 1,
+            sets.len(),
+            entry.wins,
+            entry.draw_rate(),
+        );
+    }
+
+    match output {
+        Some(output) => {
+            let w = BufWriter::new(File::create(&output).expect("could not create output file"));
+            atlas.write_csv(w).expect("could not write atlas");
+            println!("saved atlas to {output}");
+        }
+        None => atlas
+            .write_csv(std::io::stdout())
+            .expect("could not write atlas"),
+    }
+}
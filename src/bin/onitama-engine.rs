@@ -0,0 +1,86 @@
+use std::{
+    env::args,
+    io::{stdin, stdout, BufRead, Write},
+};
+
+use onitama_solver::onitama_simd::{AllTables, Position, Wdl};
+
+const USAGE: &str = "expected args: tables, the tables are saved by table_base --dtc --out";
+
+// a perfect player for the positions of stored tables
+// it reads one command per line from stdin and answers with one line on stdout
+// `ready` is written once the tables are loaded
+// the tables need the distances, without them a won position might never be finished
+//
+// `position <notation>` sets the position, see [Position] for the notation
+// `moves` answers `moves` followed by every legal move, like `moves Ox:7-12 Boar:7-2`
+// `eval` answers `eval win`, `eval loss` or `eval draw` for the player to move
+// followed by the distance to conversion in plies, like `eval win 5`
+// that is the number of plies until the game ends or a piece is taken
+// it answers `eval over` if the game is over and `eval unknown` if the position is not in the tables
+// `go` answers `bestmove` followed by the move that keeps the best result, see [AllTables::best_move]
+// or `bestmove none` if there is no such move
+// `quit` stops the engine
+// anything that can not be done is answered with `error` and the reason
+pub fn main() {
+    let path = args().nth(1).expect(USAGE);
+    let tb = AllTables::load(&path).expect("could not load tables");
+    if !tb.has_dtc() {
+        panic!("the tables have no distances, build them with table_base --dtc");
+    }
+
+    let mut out = stdout().lock();
+    writeln!(out, "ready").unwrap();
+    out.flush().unwrap();
+
+    let mut position: Option<Position> = None;
+    for line in stdin().lock().lines() {
+        let line = line.expect("could not read stdin");
+        let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let answer = match command {
+            "" => continue,
+            "quit" => break,
+            "position" => match rest.parse() {
+                Ok(pos) => {
+                    position = Some(pos);
+                    continue;
+                }
+                Err(err) => format!("error {err}"),
+            },
+            "moves" | "eval" | "go" => match &position {
+                None => "error no position was given".to_string(),
+                Some(pos) => match command {
+                    "moves" => {
+                        let moves: Vec<String> =
+                            pos.moves().iter().map(ToString::to_string).collect();
+                        format!("moves {}", moves.join(" ")).trim_end().to_string()
+                    }
+                    "eval" => eval(&tb, pos),
+                    _ => match tb.best_move(pos) {
+                        Some(mv) => format!("bestmove {mv}"),
+                        None => "bestmove none".to_string(),
+                    },
+                },
+            },
+            _ => format!("error unknown command {command}"),
+        };
+        writeln!(out, "{answer}").unwrap();
+        out.flush().unwrap();
+    }
+}
+
+fn eval(tb: &AllTables, pos: &Position) -> String {
+    if pos.winner().is_some() {
+        return "eval over".to_string();
+    }
+    let Some(wdl) = tb.probe_wdl(pos) else {
+        return "eval unknown".to_string();
+    };
+    let result = match wdl {
+        Wdl::Win => "win",
+        Wdl::Loss => "loss",
+        Wdl::Draw => return "eval draw".to_string(),
+    };
+    let dtc = tb.probe_dtc(pos).expect("the tables store distances");
+    format!("eval {result} {dtc}")
+}
@@ -0,0 +1,151 @@
+use std::{
+    env::args,
+    io::{stdin, stdout, BufRead, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use onitama_solver::onitama_simd::{AllTables, Move, Position, Wdl};
+
+const USAGE: &str =
+    "expected args: [--both] tables [position], the tables are saved by table_base --dtc --out";
+
+// play against the solver in the terminal, starting from a position of the tables
+// a random position is used if none is given, see [Position] for the notation
+// you play the side that is to move at the start and the solver plays the other side
+// with `--both` you make the moves of both sides
+// every turn shows the result of every move, pick one by its number or write it like `Ox:7-12`
+// `undo` takes back your last move together with the answer of the solver and `quit` stops
+pub fn main() {
+    let mut both = false;
+    let mut positional = vec![];
+    for arg in args().skip(1) {
+        match arg.as_str() {
+            "--both" => both = true,
+            flag if flag.starts_with("--") => panic!("unknown flag {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+    let (path, position) = positional.split_first().expect(USAGE);
+    let tb = AllTables::load(path).expect("could not load tables");
+    // without distances the solver keeps a win but might never finish the game
+    if !tb.has_dtc() {
+        panic!("the tables have no distances, build them with table_base --dtc");
+    }
+
+    let mut pos: Position = if position.is_empty() {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        tb.sampler(seed as u64)
+            .next()
+            .expect("the tables are empty")
+            .position
+    } else {
+        position
+            .join(" ")
+            .parse()
+            .unwrap_or_else(|err| panic!("{err}"))
+    };
+    let human = pos.to_move;
+    let mut history = vec![];
+    let mut input = stdin().lock().lines();
+
+    loop {
+        println!();
+        // the board says who won, the notation is not shown because a taken king is not written
+        if pos.winner().is_some() {
+            print!("{}", pos.pretty());
+            return;
+        }
+        println!("{pos}");
+        print!("{}", pos.pretty());
+        match (tb.probe_wdl(&pos), tb.probe_dtc(&pos)) {
+            (Some(wdl), Some(dtc)) if wdl != Wdl::Draw => {
+                println!("{wdl:?}, converts in {dtc} plies")
+            }
+            (Some(wdl), _) => println!("{wdl:?}"),
+            (None, _) => println!("the position is not part of the tables"),
+        }
+
+        if !both && pos.to_move != human {
+            let Some(mv) = tb.best_move(&pos) else {
+                if tb.probe_wdl(&pos).is_none() {
+                    println!("the position is not part of the tables");
+                } else {
+                    println!("the solver has no move and loses");
+                }
+                return;
+            };
+            println!("the solver plays {mv}");
+            pos = pos.play(mv);
+            continue;
+        }
+
+        let results = tb.move_results(&pos);
+        if results.is_empty() {
+            println!("there are no moves, so you lose");
+            return;
+        }
+        for (i, (mv, result)) in results.iter().enumerate() {
+            println!("{:3}. {mv:16} {}", i + 1, describe(&tb, &pos, *mv, *result));
+        }
+
+        pos = loop {
+            print!("> ");
+            stdout().flush().unwrap();
+            let Some(line) = input.next() else {
+                return;
+            };
+            let line = line.expect("could not read stdin");
+            let line = line.trim();
+            let mv = match line {
+                "quit" => return,
+                "undo" => match history.pop() {
+                    Some(prev) => break prev,
+                    None => {
+                        println!("there is nothing to undo");
+                        continue;
+                    }
+                },
+                _ => match line.parse::<usize>() {
+                    Ok(i) => match results.get(i.wrapping_sub(1)) {
+                        Some(&(mv, _)) => mv,
+                        None => {
+                            println!("pick a number from 1 to {}", results.len());
+                            continue;
+                        }
+                    },
+                    Err(_) => match line.parse::<Move>() {
+                        Ok(mv) if results.iter().any(|&(m, _)| m == mv) => mv,
+                        Ok(mv) => {
+                            println!("{mv} is not a legal move");
+                            continue;
+                        }
+                        Err(err) => {
+                            println!("{err}");
+                            continue;
+                        }
+                    },
+                },
+            };
+            history.push(pos);
+            break pos.play(mv);
+        };
+    }
+}
+
+// the result of a move for the player that makes it
+fn describe(tb: &AllTables, pos: &Position, mv: Move, result: Option<Wdl>) -> String {
+    let Some(wdl) = result else {
+        return "unknown".to_string();
+    };
+    if pos.play(mv).winner().is_some() {
+        return "wins at once".to_string();
+    }
+    match tb.move_dtc(pos, mv) {
+        Some(1) if wdl != Wdl::Draw => format!("{wdl:?}, converts in 1 ply"),
+        Some(dtc) if wdl != Wdl::Draw => format!("{wdl:?}, converts in {dtc} plies"),
+        _ => format!("{wdl:?}"),
+    }
+}
@@ -0,0 +1,41 @@
+use std::{env::args, fs::File, io::BufWriter};
+
+use onitama_solver::onitama_simd::AllTables;
+
+const USAGE: &str =
+    "expected args: [--seed n] [--out file] tables count, the tables are saved by table_base --out";
+
+// writes random positions of stored tables with their label, see [onitama_simd::Sample]
+pub fn main() {
+    let mut seed = 0;
+    let mut output = None;
+    let mut positional = vec![];
+    let mut iter = args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--seed" => seed = iter.next().expect(USAGE).parse().expect("expected integer"),
+            "--out" => output = Some(iter.next().expect(USAGE)),
+            flag if flag.starts_with("--") => panic!("unknown flag {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+    let [tables, count] = &positional[..] else {
+        panic!("{USAGE}");
+    };
+    let count: usize = count.parse().expect("expected integer");
+
+    let tb = AllTables::load(tables).expect("could not load tables");
+    let mut sampler = tb.sampler(seed);
+    match output {
+        Some(output) => {
+            let w = BufWriter::new(File::create(&output).expect("could not create output file"));
+            sampler
+                .write_text(count, w)
+                .expect("could not write samples");
+            eprintln!("saved {count} samples to {output}");
+        }
+        None => sampler
+            .write_text(count, std::io::stdout().lock())
+            .expect("could not write samples"),
+    }
+}
@@ -1,48 +1,169 @@
-use std::{env::args, sync::atomic::Ordering, time::Instant};
+use std::{env::args, fs::File, io::BufWriter, time::Instant};
 
-use onitama_solver::onitama_simd::AllTables;
+use bit_iter::BitIter;
+use onitama_solver::{
+    card::parse_card_set,
+    onitama_simd::{parse_size, AllTables, BuildConfig, BuildEvent, Card, Threads},
+};
+
+// ox, boar, horse, elephant and crab
+const DEFAULT_CARDS: u64 = 0b11111;
+
+const USAGE: &str =
+    "expected args: [--wdl] [--dtc] [--mirror] [--fixpoint] [--out file] [--verify file] [--stats file.json or file.csv] [--dir table dir] [--checkpoint dir] [--threads n, 0 for all cores] [--trace file] [--skip-resolved] num pieces [5 card names or indices]";
 
 pub fn main() {
-    let size = args().nth(1).expect("expected one arg: num pieces");
-    let size = match size.parse::<u8>().expect("expected integer") {
-        2 => 1,
-        4 => 2,
-        6 => 3,
-        8 => 4,
-        10 => 5,
-        _ => panic!("that size is not supported"),
+    let mut wdl = false;
+    let mut dtc = false;
+    let mut mirror = false;
+    let mut fixpoint = false;
+    let mut output = None;
+    let mut verify = None;
+    let mut stats_file = None;
+    let mut dir = None;
+    let mut checkpoint = None;
+    let mut threads = Threads::Count(1);
+    let mut trace = None;
+    let mut skip_resolved = false;
+    let mut positional = vec![];
+    let mut iter = args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--wdl" => wdl = true,
+            "--dtc" => dtc = true,
+            "--mirror" => mirror = true,
+            "--fixpoint" => fixpoint = true,
+            "--skip-resolved" => skip_resolved = true,
+            "--out" => output = Some(iter.next().expect(USAGE)),
+            "--verify" => verify = Some(iter.next().expect(USAGE)),
+            "--stats" => stats_file = Some(iter.next().expect(USAGE)),
+            "--dir" => dir = Some(iter.next().expect(USAGE)),
+            "--checkpoint" => checkpoint = Some(iter.next().expect(USAGE)),
+            "--trace" => trace = Some(iter.next().expect(USAGE)),
+            "--threads" => {
+                threads = match iter.next().expect(USAGE).parse().expect("expected integer") {
+                    0 => Threads::Global,
+                    n => Threads::Count(n),
+                }
+            }
+            flag if flag.starts_with("--") => panic!("unknown flag {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+
+    let size = positional.first().expect(USAGE);
+    let size = parse_size(size).unwrap_or_else(|err| panic!("{err}"));
+
+    let cards = match &positional[1..] {
+        [] => DEFAULT_CARDS,
+        names => parse_card_set(names).unwrap_or_else(|err| panic!("{err}")),
     };
+    let names: Vec<String> = BitIter::from(cards)
+        .map(|i| Card::new(i).unwrap().to_string())
+        .collect();
+    println!("using cards {}", names.join(", "));
 
     let before = Instant::now();
-    let tb = AllTables::build(size, 0b11111);
+    let mut config = BuildConfig::new(size, cards)
+        .wdl(wdl)
+        .dtc(dtc)
+        .mirror(mirror)
+        .threads(threads)
+        .skip_resolved(skip_resolved)
+        .observer(|event| match event {
+            BuildEvent::GoUp { counts, iteration } => {
+                println!("{counts:?} stopped making progress after {iteration} iterations")
+            }
+            BuildEvent::TableDone {
+                counts,
+                iterations,
+                elapsed,
+            } => println!(
+                "finished {counts:?} in {iterations} iterations, {:.3} seconds",
+                elapsed.as_secs_f32()
+            ),
+            _ => {}
+        });
+    if let Some(dir) = dir {
+        config = config.dir(dir);
+    }
+    if let Some(checkpoint) = checkpoint {
+        config = config.checkpoint(checkpoint);
+    }
+    if let Some(trace) = trace {
+        config = config.trace(trace);
+    }
+    let tb = config.build();
     let time = before.elapsed();
 
     let wins = tb.count_ones();
     let total = tb.len() * 30;
+    let stats = tb.stats();
     println!("{wins} total wins");
-    println!("{} wins in 1", tb.win_in1);
-    println!("{} not win in 1", total - tb.win_in1);
-    println!("{} unresolved states", tb.total_unresolved);
+    println!("{} wins in 1", stats.win_in1());
+    println!("{} not win in 1", total - stats.win_in1());
+    println!("{} unresolved states", stats.unresolved());
+    println!("{} resolved blocks were skipped", stats.skipped());
+    if tb.has_losses() {
+        println!("{} losses", tb.count_losses());
+    }
+    if tb.is_mirrored() {
+        println!("only canonical layouts are stored");
+    }
+    if tb.has_dtc() {
+        println!("longest distance to conversion is {} plies", tb.max_dtc());
+    }
     println!(
         "{} resolved, not win in 1",
-        total - tb.win_in1 - tb.total_unresolved
-    );
-    println!(
-        "{} blocks done, {} blocks not done",
-        tb.block_done.load(Ordering::Relaxed),
-        tb.block_not_done.load(Ordering::Relaxed)
-    );
-    println!(
-        "{} cards done, {} cards not done",
-        tb.card_done.load(Ordering::Relaxed),
-        tb.card_not_done.load(Ordering::Relaxed)
+        total - stats.win_in1() - stats.unresolved()
     );
+    println!("{} iterations in total", stats.iterations());
     println!("took {:.3} seconds", time.as_secs_f32());
 
-    match size {
-        2 => assert_eq!(wins, 6752579),
-        3 => assert_eq!(wins, 831344251),
-        4 => assert_eq!(wins, 37560295296),
-        _ => {}
-    };
+    if let Some(output) = output {
+        tb.save(&output).expect("could not save tables");
+        println!("saved tables to {output}");
+    }
+
+    if let Some(stats_file) = stats_file {
+        let w = BufWriter::new(File::create(&stats_file).expect("could not create stats file"));
+        if stats_file.ends_with(".csv") {
+            stats.write_csv(w)
+        } else {
+            stats.write_json(w)
+        }
+        .expect("could not write stats");
+        println!("saved stats to {stats_file}");
+    }
+
+    if fixpoint {
+        let before = Instant::now();
+        let result = tb.verify();
+        for counterexample in &result.counterexamples {
+            counterexample.print();
+        }
+        println!(
+            "checked {} states for the fixpoint in {:.3} seconds",
+            result.checked,
+            before.elapsed().as_secs_f32()
+        );
+        assert!(result.is_ok(), "{} states are wrong", result.failures);
+    }
+
+    if let Some(verify) = verify {
+        let stored = AllTables::load(&verify).expect("could not load tables");
+        assert_eq!(stored.size(), size, "stored tables have a different size");
+        assert_eq!(stored.cards(), cards, "stored tables use different cards");
+        assert!(stored == tb, "stored tables do not match");
+        println!("verified tables in {verify}");
+    }
+
+    if cards == DEFAULT_CARDS {
+        match size {
+            2 => assert_eq!(wins, 6752579),
+            3 => assert_eq!(wins, 831344251),
+            4 => assert_eq!(wins, 37560295296),
+            _ => {}
+        };
+    }
 }
@@ -1,11 +1,13 @@
 #![allow(dead_code)]
+use std::{fmt, str::FromStr};
+
 use bit_iter::BitIter;
 use seq_macro::seq;
 
 // TODO: precalculate with every possible offset if that is faster
 
 #[inline]
-pub(crate) fn cards_mask<const S: bool>(offset: u32, cards: u16) -> u32 {
+pub(crate) fn cards_mask<const S: bool>(offset: u32, cards: u64) -> u32 {
     let bitmap = get_bitmap::<S>(cards);
     offset_mask(offset as usize, bitmap)
 }
@@ -22,7 +24,7 @@ pub(crate) fn offset_mask_fixed(offset: usize, mask: u32) -> u32 {
     ((new as u64) << offset >> 12) as u32
 }
 
-/// this method is used to get the orginal mask after using [offset_mask]
+// this method is used to get the orginal mask after using [offset_mask]
 // #[inline]
 // pub(crate) fn undo_offset(offset: u8, mask: u32) -> u32 {
 //     ((mask as u64) << offset >> 12) as u32
@@ -57,9 +59,173 @@ const BOARD_MASK: [u32; 25] = [
     0b11100_11100_11100_00000_00000,
 ];
 
+// one of the cards of the base game and the expansions, the index is the bit in the card set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Card(pub(crate) usize);
+
+// the colour of the stamp on a card, the player with that colour starts the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stamp {
+    Blue,
+    Red,
+}
+
+// name and stamp of every card in the same order as the bitmaps
+// the 16 cards of the base game come first, so their card sets are unchanged
+const CATALOGUE: [(&str, Stamp); 34] = [
+    ("Ox", Stamp::Blue),
+    ("Boar", Stamp::Red),
+    ("Horse", Stamp::Red),
+    ("Elephant", Stamp::Red),
+    ("Crab", Stamp::Blue),
+    ("Tiger", Stamp::Blue),
+    ("Monkey", Stamp::Blue),
+    ("Crane", Stamp::Blue),
+    ("Dragon", Stamp::Red),
+    ("Mantis", Stamp::Red),
+    ("Frog", Stamp::Red),
+    ("Rabbit", Stamp::Blue),
+    ("Goose", Stamp::Blue),
+    ("Rooster", Stamp::Red),
+    ("Eel", Stamp::Blue),
+    ("Cobra", Stamp::Red),
+    // sensei's path
+    ("Tanuki", Stamp::Blue),
+    ("Iguana", Stamp::Red),
+    ("Sable", Stamp::Blue),
+    ("Otter", Stamp::Red),
+    ("Fox", Stamp::Red),
+    ("Dog", Stamp::Blue),
+    ("Giraffe", Stamp::Blue),
+    ("Kirin", Stamp::Red),
+    ("Rat", Stamp::Red),
+    ("Mouse", Stamp::Blue),
+    ("Phoenix", Stamp::Blue),
+    ("Turtle", Stamp::Red),
+    ("Panda", Stamp::Red),
+    ("Bear", Stamp::Blue),
+    ("Sea Snake", Stamp::Blue),
+    ("Viper", Stamp::Red),
+    // promo cards
+    ("Goat", Stamp::Red),
+    ("Sheep", Stamp::Blue),
+];
+
+impl Card {
+    pub const COUNT: usize = CATALOGUE.len();
+
+    pub fn new(index: usize) -> Option<Self> {
+        (index < Self::COUNT).then_some(Self(index))
+    }
+
+    // all cards ordered by index
+    pub fn all() -> impl Iterator<Item = Card> {
+        (0..Self::COUNT).map(Card)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    pub fn name(self) -> &'static str {
+        CATALOGUE[self.0].0
+    }
+
+    pub fn stamp(self) -> Stamp {
+        CATALOGUE[self.0].1
+    }
+
+    // the moves as (right, forward) from the view of the player holding the card
+    // ordered from the front row to the back row and from left to right
+    pub fn offsets(self) -> Vec<(i32, i32)> {
+        let bitmap = self.bitmap::<false>();
+        (0..25i32)
+            .rev()
+            .filter(|bit| bitmap & 1 << bit != 0)
+            .map(|bit| {
+                // the bitmap is centered on square 12 and has the leftmost column in the high bits
+                let v = bit - 12;
+                let dy = (v + 2).div_euclid(5);
+                (5 * dy - v, dy)
+            })
+            .collect()
+    }
+
+    // the moves drawn on a 5x5 grid with the front row at the top
+    // the piece is drawn as `o` and the squares it can move to as `x`
+    pub fn diagram(self) -> String {
+        let bitmap = self.bitmap::<false>();
+        let mut out = String::new();
+        for y in 0..5 {
+            for x in 0..5 {
+                let i = 24 - 5 * y - x;
+                out.push(if i == 12 {
+                    'o'
+                } else if bitmap & 1 << i != 0 {
+                    'x'
+                } else {
+                    '.'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // the card with the moves flipped from left to right, this can be the card itself
+    pub fn mirror(self) -> Card {
+        let bitmap = mirror_board(self.bitmap::<false>());
+        Card::all()
+            .find(|card| card.bitmap::<false>() == bitmap)
+            .expect("every card has a mirror")
+    }
+
+    pub(crate) fn bitmap<const S: bool>(self) -> u32 {
+        get_one_bitmap::<S>(self.0)
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// accepts the index of the card or its name, ignoring case
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(index) = s.parse::<usize>() {
+            return Card::new(index).ok_or_else(|| format!("card index {index} is too large"));
+        }
+        // names with a space can also be written without it or with a dash
+        let key = |name: &str| name.replace([' ', '-', '_'], "").to_ascii_lowercase();
+        Card::all()
+            .find(|card| key(card.name()) == key(s))
+            .ok_or_else(|| format!("unknown card {s}"))
+    }
+}
+
+// a set of 5 different cards, given by their names or indices
+pub fn parse_card_set<S: AsRef<str>>(names: &[S]) -> Result<u64, String> {
+    let mut cards = 0u64;
+    for name in names {
+        let card: Card = name.as_ref().parse()?;
+        if cards & 1 << card.index() != 0 {
+            return Err(format!("card {} was chosen twice", name.as_ref()));
+        }
+        cards |= 1 << card.index();
+    }
+    if cards.count_ones() != 5 {
+        return Err(format!("expected 5 cards, got {}", cards.count_ones()));
+    }
+    Ok(cards)
+}
+
 pub(crate) fn get_one_bitmap<const S: bool>(card: usize) -> u32 {
     #[allow(clippy::unusual_byte_groupings)]
-    const CARD_MAP_0: [u32; 16] = [
+    const CARD_MAP_0: [u32; Card::COUNT] = [
         0b00000_00100_00010_00100_00000,
         0b00000_00100_01010_00000_00000,
         0b00000_00100_01000_00100_00000,
@@ -76,8 +242,30 @@ pub(crate) fn get_one_bitmap<const S: bool>(card: usize) -> u32 {
         0b00000_00010_01010_01000_00000,
         0b00000_01000_00010_01000_00000,
         0b00000_00010_01000_00010_00000,
+        // sensei's path
+        0b00000_00101_00000_01000_00000,
+        0b00000_10100_00000_00010_00000,
+        0b00000_00010_10000_01000_00000,
+        0b00000_01000_00001_00010_00000,
+        0b00000_00010_00010_00010_00000,
+        0b00000_01000_01000_01000_00000,
+        0b00000_10001_00000_00100_00000,
+        0b01010_00000_00000_00000_00100,
+        0b00000_00100_01000_00010_00000,
+        0b00000_00100_00010_01000_00000,
+        0b00000_01010_10001_00000_00000,
+        0b00000_00000_10001_01010_00000,
+        0b00000_00110_00000_01000_00000,
+        0b00000_01100_00000_00010_00000,
+        0b00000_00100_00001_01000_00000,
+        0b00000_00100_10000_00010_00000,
+        // promo cards
+        0b00000_00010_01000_00100_00000,
+        0b00000_01000_00010_00100_00000,
     ];
-    const CARD_MAP_1: [u32; 16] = seq!(C in 0..16 {
+    // `seq!` needs a literal, so it has to be changed together with the catalogue
+    const _: () = assert!(Card::COUNT == 34);
+    const CARD_MAP_1: [u32; Card::COUNT] = seq!(C in 0..34 {
         [
             #(reverse_bitmap(CARD_MAP_0[C]),)*
         ]
@@ -85,8 +273,15 @@ pub(crate) fn get_one_bitmap<const S: bool>(card: usize) -> u32 {
     [CARD_MAP_0, CARD_MAP_1][S as usize][card]
 }
 
+// flips the board from left to right
+pub(crate) fn mirror_board(board: u32) -> u32 {
+    (0..5)
+        .map(|row| (board >> (5 * row) & 0b11111).reverse_bits() >> 27 << (5 * row))
+        .fold(0, |acc, x| acc | x)
+}
+
 // card masks are ordered from top to bottom
-pub(crate) fn get_bitmap<const S: bool>(cards: u16) -> u32 {
+pub(crate) fn get_bitmap<const S: bool>(cards: u64) -> u32 {
     let mut mask = 0;
     BitIter::from(cards).for_each(|card| mask |= get_one_bitmap::<S>(card));
     mask
@@ -99,7 +294,7 @@ const fn reverse_bitmap(board: u32) -> u32 {
 
 #[cfg(test)]
 mod tests {
-    use super::cards_mask;
+    use super::{cards_mask, parse_card_set, Card, Stamp};
 
     #[test]
     pub fn test() {
@@ -118,4 +313,46 @@ mod tests {
         assert_eq!(res10_rev, mask10_rev);
         assert_eq!(res3_rev, mask3_rev);
     }
+
+    #[test]
+    fn catalogue() {
+        let tiger: Card = "tiger".parse().unwrap();
+        assert_eq!(tiger.offsets(), [(0, 2), (0, -1)]);
+        assert_eq!(tiger.stamp(), Stamp::Blue);
+        assert_eq!(tiger.diagram(), "..x..\n.....\n..o..\n..x..\n.....\n");
+
+        let frog: Card = "FROG".parse().unwrap();
+        assert_eq!(frog.offsets(), [(-1, 1), (-2, 0), (1, -1)]);
+        assert_eq!(frog.stamp(), Stamp::Red);
+        assert_eq!("10".parse(), Ok(frog));
+
+        // every card has a unique set of moves and parses back from its name
+        for card in Card::all() {
+            assert_eq!(card.to_string().parse(), Ok(card));
+            assert!(Card::all().all(|c| c == card || c.offsets() != card.offsets()));
+        }
+        assert_eq!(Card::all().filter(|c| c.stamp() == Stamp::Red).count(), 17);
+        assert!("34".parse::<Card>().is_err());
+        assert!("cat".parse::<Card>().is_err());
+
+        // expansion cards come after the base game
+        let kirin: Card = "kirin".parse().unwrap();
+        assert_eq!(kirin.index(), 23);
+        assert_eq!(kirin.offsets(), [(-1, 2), (1, 2), (0, -2)]);
+        assert_eq!("sea-snake".parse::<Card>().unwrap().name(), "Sea Snake");
+
+        assert_eq!(
+            parse_card_set(&["ox", "1", "horse", "Elephant", "crab"]),
+            Ok(0b11111)
+        );
+        assert!(parse_card_set(&["ox", "ox", "horse", "elephant", "crab"]).is_err());
+        assert!(parse_card_set(&["ox", "boar"]).is_err());
+
+        assert_eq!(tiger.mirror(), tiger);
+        assert_eq!(frog.mirror(), "rabbit".parse().unwrap());
+        for card in Card::all() {
+            assert_eq!(card.mirror().mirror(), card);
+            assert_eq!(card.mirror().stamp() == card.stamp(), card.mirror() == card);
+        }
+    }
 }
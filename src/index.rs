@@ -10,6 +10,10 @@ pub(crate) trait Indexer: Clone + InternalIter {
 
     fn total(&self) -> usize;
 
+    // the item with this index, the inverse of [Indexer::index]
+    // the index has to be smaller than [Indexer::total]
+    fn unindex(&self, index: usize) -> Self::Item;
+
     fn choose_one<V, M>(self, proj: V, mask: M) -> Flatten<Self, V, M, ChooseOne>
     where
         V: Proj<Self::Item, Output = u32>,
@@ -23,6 +27,7 @@ pub(crate) trait Indexer: Clone + InternalIter {
         }
     }
 
+    #[allow(dead_code)]
     fn choose<V, M>(self, n: u32, proj: V, mask: M) -> Flatten<Self, V, M, ChooseExact>
     where
         V: Proj<Self::Item>,
@@ -66,6 +71,11 @@ impl<T: Clone> Indexer for Empty<T> {
     fn total(&self) -> usize {
         1
     }
+
+    fn unindex(&self, index: usize) -> Self::Item {
+        debug_assert_eq!(index, 0);
+        self.0.clone()
+    }
 }
 
 #[derive(Clone)]
@@ -161,12 +171,23 @@ where
     fn total(&self) -> usize {
         self.outer.total() * self.gen.total(self.mask.get_size())
     }
+
+    fn unindex(&self, index: usize) -> Self::Item {
+        let gen_total = self.gen.total(self.mask.get_size());
+        let mut item = self.outer.unindex(index / gen_total);
+        let mask: M::Output = self.mask.get_mask(&item);
+        debug_assert_eq!(mask.count_ones(), self.mask.get_size());
+
+        *self.proj.proj_mut(&mut item) = self.gen.unindex(mask, index % gen_total);
+        item
+    }
 }
 
 trait Gen<M, F>: Clone {
     type GenIter: Iterator<Item = F>;
     fn gen_iter(&self, mask: M) -> Self::GenIter;
     fn index(&self, mask: M, field: &F) -> usize;
+    fn unindex(&self, mask: M, index: usize) -> F;
     fn total(&self, mask_size: u32) -> usize;
 }
 
@@ -187,11 +208,17 @@ impl Gen<u32, u32> for ChooseOne {
         (mask_less & mask).count_ones() as usize
     }
 
+    fn unindex(&self, mask: u32, index: usize) -> u32 {
+        nth_bit(mask, index as u32)
+    }
+
     fn total(&self, mask_size: u32) -> usize {
         mask_size as usize
     }
 }
 
+// only the tests choose more than one square, the pawns are indexed in [crate::onitama_simd]
+#[allow(dead_code)]
 #[derive(Clone, Copy)]
 pub struct ChooseExact {
     count: u32,
@@ -242,6 +269,10 @@ macro_rules! gen_impl {
                 index_exact(*vals as u32, mask as u32)
             }
 
+            fn unindex(&self, mask: $t, index: usize) -> $t {
+                unindex_exact(index, mask as u32, self.count) as $t
+            }
+
             fn total(&self, mask_size: u32) -> usize {
                 comb_exact(mask_size, self.count as u32)
             }
@@ -251,6 +282,7 @@ macro_rules! gen_impl {
 
 gen_impl! { u16 u32 }
 
+#[allow(dead_code)]
 fn index_exact(vals: u32, mask: u32) -> usize {
     let mut i = 0;
     BitIter::from(vals).enumerate().for_each(|(count, offset)| {
@@ -261,6 +293,34 @@ fn index_exact(vals: u32, mask: u32) -> usize {
     i
 }
 
+// the inverse of [index_exact], the chosen bits are found from the highest down
+// every bit is the highest one that keeps the index of the lower bits in range
+#[allow(dead_code)]
+fn unindex_exact(mut index: usize, mask: u32, count: u32) -> u32 {
+    let mut vals = 0;
+    let mut num_less = mask.count_ones();
+    for count in (1..=count).rev() {
+        num_less -= 1;
+        while comb_exact(num_less, count) > index {
+            num_less -= 1;
+        }
+        index -= comb_exact(num_less, count);
+        vals |= 1 << nth_bit(mask, num_less);
+    }
+    debug_assert_eq!(index, 0);
+    vals
+}
+
+// the offset of the set bit of `mask` that has `n` set bits below it
+fn nth_bit(mut mask: u32, n: u32) -> u32 {
+    debug_assert!(n < mask.count_ones());
+    for _ in 0..n {
+        mask &= mask - 1;
+    }
+    mask.trailing_zeros()
+}
+
+#[allow(dead_code)]
 fn comb_exact(num_less: u32, count: u32) -> usize {
     const fn comb_exact_inner(num_less: usize, count: usize) -> usize {
         if count > num_less {
@@ -327,7 +387,27 @@ mod tests {
                 (|b: &Two| 0b1111u32 & !b.one & !b.two, 2),
             );
         for (i, x) in indexer.clone().into_iter().enumerate() {
-            assert_eq!(i, indexer.index(&x))
+            assert_eq!(i, indexer.index(&x));
+            let y = indexer.unindex(i);
+            assert_eq!((x.one, x.two, x.three), (y.one, y.two, y.three));
+        }
+    }
+
+    #[test]
+    fn unindex_gen() {
+        let mask = 0b1011_0110_1101u32;
+        for count in 0..6 {
+            let indexer = ChooseExact { count };
+            let mut n = 0;
+            for x in indexer.gen_iter(mask) {
+                let i = indexer.index(mask, &x);
+                assert_eq!(indexer.unindex(mask, i), x);
+                n += 1;
+            }
+            assert_eq!(n, Gen::<u32, u32>::total(&indexer, mask.count_ones()));
+        }
+        for (i, x) in ChooseOne.gen_iter(mask).enumerate() {
+            assert_eq!(ChooseOne.unindex(mask, i), x);
         }
     }
 }
@@ -1,7 +1,7 @@
 #![feature(impl_trait_in_assoc_type)]
 
 // mod anf;
-mod card;
+pub mod card;
 mod index;
 // mod onitama;
 // mod ply;
@@ -1,21 +1,53 @@
 #![allow(dead_code)]
 mod accum_spread;
+mod atlas;
+mod checkpoint;
+mod config;
 mod iter;
 mod job;
+mod mirror;
+mod moves;
+mod notation;
+mod position;
+mod reference;
+mod sample;
+mod schedule;
+mod stats;
+mod storage;
+mod store;
+mod trace;
 mod update;
+mod verify;
 
 use std::{
     alloc::Layout,
+    io,
     iter::zip,
     ops::{BitAnd, Index, IndexMut},
-    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    path::Path,
+    sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering},
     time::Instant,
 };
 
 use bit_iter::BitIter;
+use rayon::prelude::*;
+
+pub use crate::card::Card;
+pub use atlas::{base_card_sets, Atlas, AtlasEntry};
+use checkpoint::Progress;
+pub use config::{parse_size, BuildConfig, BuildEvent, Threads};
+use mirror::Canonical;
+pub use moves::Move;
+pub use position::{Position, Wdl};
+pub use reference::Reference;
+pub use sample::{Sample, Sampler};
+pub use stats::{BuildStats, TableStats};
+use storage::Storage;
+use trace::Trace;
+pub use verify::{Counterexample, Verification, Violation};
 
 use crate::{
-    card::{get_one_bitmap, offset_mask_fixed as offset_mask},
+    card::offset_mask_fixed as offset_mask,
     index::{Empty, Indexer, InternalIter},
     proj,
 };
@@ -39,6 +71,15 @@ pub struct PawnCount {
 }
 
 impl PawnCount {
+    // number of pawns of player 0, the king is not included
+    pub fn count0(self) -> u32 {
+        self.count0
+    }
+
+    pub fn count1(self) -> u32 {
+        self.count1
+    }
+
     fn invert(self) -> Self {
         Self {
             count0: self.count1,
@@ -112,15 +153,14 @@ impl KingPos {
 pub struct AllTables {
     size: u32,
     cards: Cards,
+    wdl: bool,
+    dtc: bool,
     mask_lookup: [u32; 25],
     directions: u32,
     list: Box<[Table]>,
-    pub block_done: AtomicU64,
-    pub block_not_done: AtomicU64,
-    pub card_done: AtomicU64,
-    pub card_not_done: AtomicU64,
-    pub total_unresolved: u64,
-    pub win_in1: u64,
+    // only the canonical layouts are stored, see [BuildConfig::mirror]
+    mirrored: bool,
+    stats: BuildStats,
 }
 
 impl AllTables {
@@ -130,10 +170,47 @@ impl AllTables {
         unsafe { self.list.get(i).unwrap_unchecked() }
     }
 
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn cards(&self) -> u64 {
+        self.cards.0
+    }
+
     pub fn count_ones(&self) -> u64 {
         self.list.iter().map(|x| x.count_ones()).sum()
     }
 
+    // the results of the build for every pawn count, see [BuildStats]
+    pub fn stats(&self) -> &BuildStats {
+        &self.stats
+    }
+
+    // whether the proven losses are stored, see [BuildConfig::wdl]
+    pub fn has_losses(&self) -> bool {
+        self.wdl
+    }
+
+    pub fn count_losses(&self) -> u64 {
+        self.list.iter().map(|x| x.count_losses()).sum()
+    }
+
+    // whether the distances are stored, see [BuildConfig::dtc]
+    pub fn has_dtc(&self) -> bool {
+        self.dtc
+    }
+
+    // the largest distance of any won or lost state
+    pub fn max_dtc(&self) -> u8 {
+        let max = |t: &Table| t.dtc.iter().map(|x| x.load(Ordering::Relaxed)).max();
+        self.list.iter().filter_map(max).max().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn len(&self) -> u64 {
         let mut total = 0;
         for table in self.list.iter() {
@@ -149,16 +226,74 @@ impl AllTables {
 pub struct Table {
     counts: PawnCount,
     chunk_size: usize,
-    list: Box<[AtomicU32]>,
+    list: Storage<AtomicU32>,
+    // same layout as `list`, but contains the lost states
+    // this is empty if the losses are not stored
+    losses: Storage<AtomicU32>,
+    // wins that are found during an iteration are collected here when the distances are stored
+    // they are only added to `list` after the iteration, so that every iteration is one move deeper
+    pending: Storage<AtomicU32>,
+    // distance to conversion of every state in plies, 30 entries per block, see [BuildConfig::dtc]
+    // 0 means that the state is not resolved, wins are odd and losses are even
+    dtc: Storage<AtomicU8>,
+    // when the table is reduced by mirror symmetry, only the canonical layouts are stored
+    canonical: Option<Canonical>,
 }
 
 impl Table {
+    fn new(counts: PawnCount, wdl: bool, dtc: bool, dir: Option<&Path>) -> io::Result<Self> {
+        let chunk_size = (counts.count0 + 1) as usize * (counts.count1 + 1) as usize;
+        let num_chunks = counts.total();
+        let len = chunk_size * num_chunks;
+        let name = |plane: &str| format!("{}_{}.{plane}", counts.count0, counts.count1);
+        Ok(Table {
+            counts,
+            chunk_size,
+            list: Storage::new(dir, &name("wins"), len)?,
+            losses: Storage::new(dir, &name("losses"), if wdl { len } else { 0 })?,
+            pending: Storage::new(dir, &name("pending"), if dtc { len } else { 0 })?,
+            dtc: Storage::new(dir, &name("dtc"), if dtc { len * 30 } else { 0 })?,
+            canonical: None,
+        })
+    }
+
     fn index(&self, layout: TeamLayout) -> SubTable<'_> {
+        self.sub_table(&self.list, layout)
+    }
+
+    fn index_losses(&self, layout: TeamLayout) -> SubTable<'_> {
+        debug_assert!(!self.losses.is_empty());
+        self.sub_table(&self.losses, layout)
+    }
+
+    fn index_pending(&self, layout: TeamLayout) -> SubTable<'_> {
+        debug_assert!(!self.pending.is_empty());
+        self.sub_table(&self.pending, layout)
+    }
+
+    // position of the chunk of the layout in `list`
+    fn layout_index(&self, layout: TeamLayout) -> usize {
         let i = self.counts.index(&layout);
+        match &self.canonical {
+            None => i,
+            Some(canonical) => canonical.rank(i),
+        }
+    }
+
+    // position of the block in `list`
+    fn block_index(&self, layout: TeamLayout, kpos: KingPos) -> usize {
+        let i = self.layout_index(layout);
+        self.chunk_size * i + layout.indexer(self.counts).index(&kpos)
+    }
 
-        let slice = self
-            .list
-            .get(self.chunk_size * i..self.chunk_size * (i + 1));
+    fn dtc(&self, block: usize, bit: u32) -> &AtomicU8 {
+        &self.dtc[block * 30 + bit as usize]
+    }
+
+    fn sub_table<'a>(&'a self, list: &'a [AtomicU32], layout: TeamLayout) -> SubTable<'a> {
+        let i = self.layout_index(layout);
+
+        let slice = list.get(self.chunk_size * i..self.chunk_size * (i + 1));
         let slice = unsafe { slice.unwrap_unchecked() };
         SubTable {
             layout,
@@ -168,13 +303,76 @@ impl Table {
     }
 
     fn count_ones(&self) -> u64 {
-        self.list
-            .iter()
-            .map(|x| x.load(Ordering::Relaxed).bitand(BLOCK_MASK).count_ones() as u64)
+        self.count_chunks(&self.list)
+    }
+
+    fn count_losses(&self) -> u64 {
+        self.count_chunks(&self.losses)
+    }
+
+    // layouts that are not their own mirror count for both themselves and their mirror
+    fn count_chunks(&self, list: &[AtomicU32]) -> u64 {
+        let Some(canonical) = &self.canonical else {
+            return count_blocks(list);
+        };
+        zip(list.chunks(self.chunk_size), canonical.iter())
+            .map(|(chunk, i)| {
+                let symmetric = self.counts.index(&self.counts.unindex(i).mirror()) == i;
+                count_blocks(chunk) << !symmetric as u32
+            })
             .sum()
     }
 }
 
+// compares the results, not the statistics of the build
+impl PartialEq for AllTables {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.cards.0 == other.cards.0
+            && self.wdl == other.wdl
+            && self.dtc == other.dtc
+            && self.is_mirrored() == other.is_mirrored()
+            && zip(self.list.iter(), other.list.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl PartialEq for Table {
+    fn eq(&self, other: &Self) -> bool {
+        let blocks = |a: &[AtomicU32], b: &[AtomicU32]| {
+            a.len() == b.len()
+                && zip(a, b).all(|(a, b)| {
+                    let (a, b) = (a.load(Ordering::Relaxed), b.load(Ordering::Relaxed));
+                    a & BLOCK_MASK == b & BLOCK_MASK
+                })
+        };
+        let dtc = |a: &[AtomicU8], b: &[AtomicU8]| {
+            a.len() == b.len()
+                && zip(a, b).all(|(a, b)| a.load(Ordering::Relaxed) == b.load(Ordering::Relaxed))
+        };
+        self.counts == other.counts
+            && blocks(&self.list, &other.list)
+            && blocks(&self.losses, &other.losses)
+            && dtc(&self.dtc, &other.dtc)
+    }
+}
+
+fn count_blocks(list: &[AtomicU32]) -> u64 {
+    list.iter()
+        .map(|x| x.load(Ordering::Relaxed).bitand(BLOCK_MASK).count_ones() as u64)
+        .sum()
+}
+
+// only used for atomic integers, for which all zeroes is a valid value
+fn alloc_zeroed<T>(len: usize) -> Box<[T]> {
+    if len == 0 {
+        return Box::new([]);
+    }
+    unsafe {
+        let ptr = std::alloc::alloc_zeroed(Layout::array::<T>(len).unwrap());
+        Vec::from_raw_parts(ptr as *mut T, len, len).into_boxed_slice()
+    }
+}
+
 // contains results for only one layout
 #[derive(Debug, Clone, Copy)]
 pub struct SubTable<'a> {
@@ -240,6 +438,9 @@ pub struct ImmutableUpdate<'a> {
     go_up: bool,
     mask_lookup: &'a [u32; 25],
     directions: u32,
+    dtc: bool,
+    skip_resolved: bool,
+    iteration: u16,
 }
 
 pub struct Update<'a> {
@@ -256,6 +457,8 @@ pub struct Accum<'a> {
     mask: u32,
     step: (usize, usize),
     slice: &'a mut [u32],
+    // the wins of the old layout, only read to skip blocks with the RESOLVED_BIT
+    wins: &'a [u32],
     king_lookup: &'a KingLookup,
 }
 
@@ -264,6 +467,7 @@ pub struct Spread<'a> {
     current: &'a Table,
     leave_one: Option<&'a Table>,
     go_up: bool,
+    deferred: bool,
     step: (usize, usize),
     slice: &'a [u32],
     king_lookup: &'a KingLookup,
@@ -296,7 +500,21 @@ impl AllTables {
         }
     }
 
-    pub fn build(size: u32, cards: u16) -> Self {
+    // makes the table of every pawn count up to `size` with `table`, one after the other
+    // fails if `cards` is not a set of 5 known cards
+    fn new(
+        size: u32,
+        cards: u64,
+        wdl: bool,
+        dtc: bool,
+        table: impl FnMut(PawnCount) -> io::Result<Table>,
+    ) -> io::Result<Self> {
+        if cards.count_ones() != 5 || cards >> Card::COUNT != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid card set {cards:#b}"),
+            ));
+        }
         let mut mask_lookup = [0; 25];
         let mut directions = 0;
         for (mask, card) in zip(mask_iter(), Cards(cards).iter()) {
@@ -306,81 +524,227 @@ impl AllTables {
             directions |= card.bitmap::<false>();
         }
 
-        let mut tb = Self {
+        Ok(Self {
             size,
             cards: Cards(cards),
+            wdl,
+            dtc,
             mask_lookup,
             directions,
             list: count_indexer(size)
                 .into_iter()
-                .map(|counts: PawnCount| {
-                    let chunk_size = (counts.count0 + 1) as usize * (counts.count1 + 1) as usize;
-                    let num_chunks = counts.total();
-                    let len = chunk_size * num_chunks;
-                    let list = unsafe {
-                        let ptr =
-                            std::alloc::alloc_zeroed(Layout::array::<AtomicU32>(len).unwrap());
-                        Vec::from_raw_parts(ptr as *mut AtomicU32, len, len).into_boxed_slice()
-                    };
-                    Table {
-                        counts,
-                        chunk_size,
-                        list,
-                    }
-                })
-                .collect(),
-            block_done: Default::default(),
-            block_not_done: Default::default(),
-            card_done: Default::default(),
-            card_not_done: Default::default(),
-            total_unresolved: 0,
-            win_in1: 0,
+                .map(table)
+                .collect::<io::Result<_>>()?,
+            mirrored: false,
+            stats: BuildStats::new(size, cards),
+        })
+    }
+
+    pub fn build(size: u32, cards: u64) -> Self {
+        BuildConfig::new(size, cards).build()
+    }
+
+    fn build_with(config: &BuildConfig) -> io::Result<Self> {
+        let before = Instant::now();
+        let size = config.size;
+        let wdl = config.wdl || config.dtc;
+        let dir = config.dir.as_deref();
+        // the tables are backed by files in `dir` if it is given
+        let mut tb = Self::new(size, config.cards, wdl, config.dtc, |counts| {
+            Table::new(counts, wdl, config.dtc, dir)
+        })?;
+        if config.mirror && !tb.cards.is_symmetric() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "mirrored tables need a symmetric card set",
+            ));
+        }
+
+        let schedule = schedule::schedule(size);
+        let trace = config.trace.as_deref().map(Trace::new).transpose()?;
+        let mut progress = match &config.checkpoint {
+            Some(dir) => tb.read_checkpoint(dir, schedule.len())?,
+            None => Progress::new(tb.list.len()),
         };
 
-        let mut win_in1 = 0;
-        let mut schedule = vec![];
-        for counts in count_indexer(size) {
-            let counts: PawnCount = counts;
-            if counts.count0 < counts.count1 {
-                continue;
+        let end = config
+            .max_entries
+            .unwrap_or(schedule.len())
+            .min(schedule.len());
+        let mut start = progress.entries.min(end);
+        while start < end {
+            // the entries of one level are independent, so they are solved at the same time
+            let level = schedule[start].1;
+            let len = schedule[start..end]
+                .iter()
+                .take_while(|&&(_, l)| l == level)
+                .count();
+            let entries: Vec<PawnCount> = schedule[start..start + len]
+                .iter()
+                .map(|&(counts, _)| counts)
+                .collect();
+
+            // with file backed tables, the others can be written to disk
+            let mut needed: Vec<usize> = entries
+                .iter()
+                .flat_map(|&counts| schedule::entry_tables(size, counts))
+                .collect();
+            needed.sort();
+            needed.dedup();
+            tb.set_resident(&needed)?;
+
+            let stats: Vec<Vec<TableStats>> = entries
+                .par_iter()
+                .map(|&counts| tb.solve_entry(counts, config, trace.as_ref()))
+                .collect::<io::Result<_>>()?;
+            progress.tables.extend(stats.into_iter().flatten());
+
+            start += len;
+            progress.entries = start;
+            if let Some(dir) = &config.checkpoint {
+                let mut written: Vec<usize> = entries
+                    .iter()
+                    .flat_map(|&counts| schedule::entry_written(size, counts))
+                    .collect();
+                written.sort();
+                written.dedup();
+                tb.write_checkpoint(dir, &written, &mut progress)?;
             }
-            let mut jobs = vec![TableJob::new(&tb, counts)];
-            if counts.count0 > counts.count1 {
-                jobs.push(TableJob::new(&tb, counts.invert()));
+        }
+
+        if let Some(trace) = &trace {
+            trace.flush()?;
+        }
+        tb.set_resident(&[])?;
+        tb.stats.tables = progress.tables;
+        tb.stats.elapsed = before.elapsed();
+        if config.mirror {
+            tb.mirror();
+        }
+        Ok(tb)
+    }
+
+    // solves a pawn count together with its inverse
+    fn solve_entry(
+        &self,
+        counts: PawnCount,
+        config: &BuildConfig,
+        trace: Option<&Trace>,
+    ) -> io::Result<Vec<TableStats>> {
+        let start = Instant::now();
+        let mut jobs: Vec<TableJob> = counts
+            .with_inverse()
+            .map(|counts| TableJob::new(self, counts, config.skip_resolved))
+            .collect();
+        let notify = |event| {
+            if let Some(observer) = &config.observer {
+                observer.notify(event)
             }
+        };
+        for job in &jobs {
+            notify(BuildEvent::TableStart {
+                counts: job.update.current.counts,
+            });
+        }
+
+        let mut win_in1 = vec![];
+        for job in &jobs {
+            job.mark_ez_win();
+            win_in1.push(job.update.current.count_ones());
+        }
+        if self.dtc {
             for job in &jobs {
-                job.mark_ez_win();
-                win_in1 += job.update.current.count_ones();
+                job.mark_initial_dtc();
             }
-            schedule.push(jobs)
         }
 
-        let mut total_unresolved = 0;
-        for mut jobs in schedule {
-            let mut any_progress = true;
-            let mut iters = 0;
-            while any_progress {
-                any_progress = false;
-                for job in &mut jobs {
-                    any_progress |= job.next().is_some();
+        let written: Vec<usize> = schedule::entry_written(self.size, counts).collect();
+        let mut any_progress = true;
+        let mut iters: u16 = 0;
+        while any_progress {
+            any_progress = false;
+            // wins that are found before going up wait in `pending` when the distances are stored
+            let mut any_pending = false;
+            let snapshot = match trace {
+                Some(trace) => {
+                    trace.round(counts, iters as u32)?;
+                    Some((trace, trace.snapshot(self, &written)))
+                }
+                None => None,
+            };
+            for job in &mut jobs {
+                let go_up = job.update.go_up;
+                let layouts = trace.map(|_| job.layouts.clone());
+                if job.next().is_none() {
+                    continue;
+                }
+                any_progress = true;
+                any_pending |= job.progress && !go_up;
+                if let (Some(trace), Some(layouts)) = (trace, layouts) {
+                    trace.job(job, go_up, &layouts)?;
+                }
+                let counts = job.update.current.counts;
+                notify(BuildEvent::Iteration {
+                    counts,
+                    iteration: job.update.iteration,
+                    remaining: job.layouts.len(),
+                    resolved: job.resolved.len(),
+                });
+                if job.update.go_up && !go_up {
+                    let iteration = job.update.iteration;
+                    notify(BuildEvent::GoUp { counts, iteration });
                 }
-                iters += 1;
             }
-
-            for job in &jobs {
-                job.count_unresolved();
-                total_unresolved += job.total_unresolved.load(Ordering::Relaxed);
+            if let Some((trace, snapshot)) = &snapshot {
+                trace.flips(self, snapshot)?;
+            }
+            let too_far =
+                || io::Error::other(format!("{counts:?} has a distance above {}", u8::MAX));
+            if jobs.iter().any(|job| job.overflow) {
+                return Err(too_far());
+            }
+            if any_pending {
+                let dist = u8::try_from(2 * iters + 3).map_err(|_| too_far())?;
+                let snapshot = trace.map(|trace| trace.snapshot(self, &written));
+                for job in &jobs {
+                    job.merge_pending(dist);
+                }
+                if let (Some(trace), Some(snapshot)) = (trace, &snapshot) {
+                    trace.flips(self, snapshot)?;
+                }
             }
+            iters += 1;
+        }
 
-            println!(
-                "finished {:?} in {iters} iterations",
-                jobs[0].update.current.counts
-            );
+        let mut stats = vec![];
+        for (job, win_in1) in zip(&jobs, win_in1) {
+            job.count_unresolved();
+            job.clear_resolved();
+            stats.push(TableStats {
+                counts: job.update.current.counts,
+                wins: job.update.current.count_ones(),
+                win_in1,
+                unresolved: job.total_unresolved.load(Ordering::Relaxed),
+                iterations: job.update.iteration,
+                elapsed: start.elapsed(),
+                resolved: job.resolved_per_phase,
+                skipped: job.skipped,
+            });
         }
 
-        tb.total_unresolved = total_unresolved;
-        tb.win_in1 = win_in1;
-        tb
+        match &config.observer {
+            Some(observer) => {
+                for job in &jobs {
+                    observer.notify(BuildEvent::TableDone {
+                        counts: job.update.current.counts,
+                        iterations: job.update.iteration,
+                        elapsed: start.elapsed(),
+                    });
+                }
+            }
+            None => println!("finished {counts:?} in {iters} iterations"),
+        }
+        Ok(stats)
     }
 }
 
@@ -391,6 +755,14 @@ struct TableJob<'a> {
     resolved: Vec<TeamLayout>,
     update: ImmutableUpdate<'a>,
     total_unresolved: AtomicU64,
+    // number of layouts that were resolved before and after going up
+    resolved_per_phase: [u64; 2],
+    // number of blocks that were skipped because they were resolved
+    skipped: u64,
+    // whether the last iteration found new wins
+    progress: bool,
+    // whether a lost state has a distance that does not fit, see [BuildConfig::dtc]
+    overflow: bool,
     done: bool,
 }
 
@@ -422,90 +794,142 @@ impl Block {
         let data = data | data >> 30;
         Self(data as u32 & BLOCK_MASK)
     }
+
+    // returns the cards of player 0, player 1 and the side card for one bit of the block
+    // as positions in the card set
+    //
+    // every bit of [mask_iter] has the corresponding card on the side
+    // and [Block::expand] moves the side card into the hand of player 1
+    fn split(bit: u32) -> ([usize; 2], [usize; 2], usize) {
+        let side = |bit: u32| {
+            let bit = bit % 30;
+            mask_iter().position(|mask| mask & 1 << bit != 0).unwrap()
+        };
+        let (s, a, b) = (side(bit), side(bit + 10), side(bit + 20));
+        let mut hand0 = (0..5).filter(|&c| c != s && c != a && c != b);
+        let hand0 = [hand0.next().unwrap(), hand0.next().unwrap()];
+        (hand0, [a.min(b), a.max(b)], s)
+    }
+
+    // inverse of [Block::split], the order of the cards in each hand does not matter
+    fn bit(hand0: [usize; 2], hand1: [usize; 2], side: usize) -> Option<u32> {
+        let sorted = |[a, b]: [usize; 2]| [a.min(b), a.max(b)];
+        let (hand0, hand1) = (sorted(hand0), sorted(hand1));
+        (0..30).find(|&bit| Self::split(bit) == (hand0, hand1, side))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Cards(u16);
+struct Cards(u64);
 
 impl Cards {
     fn iter(self) -> impl Iterator<Item = Card> {
         BitIter::from(self.0).map(Card)
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-struct Card(usize);
-
-impl Card {
-    fn bitmap<const S: bool>(self) -> u32 {
-        get_one_bitmap::<S>(self.0)
-    }
-}
-
-fn pretty(layout: TeamLayout, kpos: KingPos) {
-    let TeamLayout { pieces0, pieces1 } = layout;
-    let KingPos { king0, king1 } = kpos;
-    debug_assert_eq!(pieces0 & pieces1, 0);
-    debug_assert_ne!(1 << king0 & pieces0, 0);
-    debug_assert_ne!(1 << king1 & pieces1, 0);
-
-    println!("----- x side");
-    for y in 0..5 {
-        for x in 0..5 {
-            let i = 24 - 5 * y - x;
-            if king0 == i {
-                print!("O")
-            } else if king1 == i {
-                print!("X")
-            } else if 1 << i & pieces0 != 0 {
-                print!("o")
-            } else if 1 << i & pieces1 != 0 {
-                print!("x")
-            } else {
-                print!(".")
-            }
-        }
-        println!()
+    // position of the card in this set, this is the order used by [mask_iter]
+    fn position(self, card: Card) -> Option<usize> {
+        self.iter().position(|c| c == card)
     }
-    println!("----- o side")
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::Ordering;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     use crate::onitama_simd::Block;
 
-    use super::{mask_iter, AllTables, PawnCount};
+    use super::{mask_iter, AllTables, BuildConfig, PawnCount, Table};
 
     #[test]
     fn build_tb() {
         let tb = AllTables::build(3, 0b11111);
         let wins = tb.count_ones();
         let total = tb.len() * 30;
+        let stats = tb.stats();
         println!("{wins} total wins");
-        println!("{} wins in 1", tb.win_in1);
-        println!("{} not win in 1", total - tb.win_in1);
-        println!("{} unresolved states", tb.total_unresolved);
+        println!("{} wins in 1", stats.win_in1());
+        println!("{} not win in 1", total - stats.win_in1());
+        println!("{} unresolved states", stats.unresolved());
         println!(
             "{} resolved, not win in 1",
-            total - tb.win_in1 - tb.total_unresolved
-        );
-        println!(
-            "{} blocks done, {} blocks not done",
-            tb.block_done.load(Ordering::Relaxed),
-            tb.block_not_done.load(Ordering::Relaxed)
-        );
-        println!(
-            "{} cards done, {} cards not done",
-            tb.card_done.load(Ordering::Relaxed),
-            tb.card_not_done.load(Ordering::Relaxed)
+            total - stats.win_in1() - stats.unresolved()
         );
+        assert_eq!(stats.wins(), wins);
         // assert_eq!(wins, 6752579);
         assert_eq!(wins, 831344251);
         // assert_eq!(wins, 27126107221);
     }
 
+    #[test]
+    fn build_expansion() {
+        // fox, giraffe, kirin, phoenix and turtle
+        let cards = 1 << 20 | 1 << 22 | 1 << 23 | 1 << 26 | 1 << 27;
+        let tb = AllTables::build(2, cards);
+        assert!(tb.count_ones() > 0);
+        // replacing the fox by the dog mirrors the board, so the results are the same
+        let mirror = AllTables::build(2, cards ^ (1 << 20 | 1 << 21));
+        assert_eq!(tb.count_ones(), mirror.count_ones());
+    }
+
+    #[test]
+    fn build_wdl() {
+        let tb = BuildConfig::new(2, 0b11111).wdl(true).build();
+        let wins = tb.count_ones();
+        let losses = tb.count_losses();
+        assert_eq!(wins, 6752579);
+        // everything that is not won or lost is a draw
+        assert_eq!(tb.len() * 30 - wins - losses, tb.stats().unresolved());
+        for table in tb.list.iter() {
+            for (w, l) in table.list.iter().zip(table.losses.iter()) {
+                assert_eq!(w.load(Ordering::Relaxed) & l.load(Ordering::Relaxed), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn build_dtc() {
+        let tb = BuildConfig::new(2, 0b11111).dtc(true).build();
+        assert_eq!(tb.count_ones(), 6752579);
+        for table in tb.list.iter() {
+            for (i, (w, l)) in table.list.iter().zip(table.losses.iter()).enumerate() {
+                let (w, l) = (w.load(Ordering::Relaxed), l.load(Ordering::Relaxed));
+                for bit in 0..30 {
+                    let dtc = table.dtc(i, bit).load(Ordering::Relaxed);
+                    if w & 1 << bit != 0 {
+                        assert_eq!(dtc % 2, 1);
+                    } else if l & 1 << bit != 0 {
+                        assert!(dtc != 0 && dtc % 2 == 0);
+                    } else {
+                        assert_eq!(dtc, 0);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn skip_resolved() {
+        let config = BuildConfig::new(2, 0b11111).dtc(true);
+        let skip = config.clone().skip_resolved(true).build();
+        let full = config.build();
+        assert!(skip.stats().skipped() > 0);
+        assert_eq!(full.stats().skipped(), 0);
+
+        let load = |list: &[AtomicU32]| -> Vec<u32> {
+            list.iter().map(|x| x.load(Ordering::Relaxed)).collect()
+        };
+        for (a, b) in skip.list.iter().zip(full.list.iter()) {
+            // the RESOLVED_BIT is removed after building
+            assert_eq!(load(&a.list), load(&b.list));
+            assert_eq!(load(&a.losses), load(&b.losses));
+            let dtc = |t: &Table| -> Vec<u8> {
+                t.dtc.iter().map(|x| x.load(Ordering::Relaxed)).collect()
+            };
+            assert_eq!(dtc(a), dtc(b));
+        }
+    }
+
     #[test]
     fn counts0() {
         for layout in PawnCount::default() {
@@ -519,4 +943,15 @@ mod tests {
             assert_eq!(mask, Block(mask).invert().0)
         }
     }
+
+    #[test]
+    fn split_test() {
+        for bit in 0..30 {
+            let (hand0, hand1, side) = Block::split(bit);
+            assert_eq!(Block::bit(hand0, hand1, side), Some(bit));
+            // inverting swaps the hands of the players
+            let inv = Block(1 << bit).invert().0.trailing_zeros();
+            assert_eq!(Block::split(inv), (hand1, hand0, side));
+        }
+    }
 }
@@ -2,10 +2,10 @@ use std::sync::atomic::Ordering;
 
 use crate::index::{InternalIter, Indexer};
 
-use super::{Accum, Spread, TeamLayout};
+use super::{Accum, Spread, TeamLayout, RESOLVED_BIT};
 
 impl Accum<'_> {
-    pub fn accumulate(self) {
+    pub fn accumulate<const SKIP: bool>(self) {
         let (from, to) = self.step;
 
         let old = self.layout;
@@ -37,9 +37,14 @@ impl Accum<'_> {
                 }
             }
 
-            let new_val = unsafe { new_slice.slice.get(new_i).unwrap_unchecked() };
             let old_i = self.king_lookup[oldk] as usize;
             debug_assert_eq!(old_i, old.indexer(self.current.counts).index(&oldk));
+            if SKIP && self.wins[old_i] & RESOLVED_BIT != 0 {
+                // the old block is already known
+                return;
+            }
+
+            let new_val = unsafe { new_slice.slice.get(new_i).unwrap_unchecked() };
             let s = unsafe { self.slice.get_mut(old_i).unwrap_unchecked() };
             // if new state is not won, then old state is not lost
             *s |= !new_val.load(Ordering::Relaxed) & self.mask;
@@ -49,7 +54,7 @@ impl Accum<'_> {
 
 impl Spread<'_> {
     // returns whether there was any progress
-    pub fn spreadout(self) -> bool {
+    pub fn spreadout<const SKIP: bool>(self) -> bool {
         let (from, to) = self.step;
 
         let old = self.layout;
@@ -71,6 +76,7 @@ impl Spread<'_> {
             self.current
         };
         let new_slice = table.index(new);
+        let pending_slice = self.deferred.then(|| table.index_pending(new));
 
         let mut progress = false;
         new.indexer(table.counts).for_enumerate(|new_i, newk| {
@@ -90,15 +96,25 @@ impl Spread<'_> {
             let old_i = self.king_lookup[oldk] as usize;
 
             let tmp = self.slice[old_i];
+            if SKIP && tmp == 0 {
+                // nothing is lost, this includes the resolved blocks
+                return;
+            }
 
             let new_val = unsafe { new_slice.slice.get(new_i).unwrap_unchecked() };
             let fetch = new_val.load(Ordering::Relaxed);
-            if fetch | tmp == fetch {
+            // a resolved block can not get new wins
+            if fetch | tmp == fetch || SKIP && fetch & RESOLVED_BIT != 0 {
                 return;
             }
 
             // if accum state is lost, then new state is won
-            new_val.fetch_or(tmp, Ordering::Relaxed);
+            if let Some(pending_slice) = pending_slice {
+                let pending = unsafe { pending_slice.slice.get(new_i).unwrap_unchecked() };
+                pending.fetch_or(tmp, Ordering::Relaxed);
+            } else {
+                new_val.fetch_or(tmp, Ordering::Relaxed);
+            }
             progress = true;
         });
 
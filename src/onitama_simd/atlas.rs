@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use crate::index::InternalIter;
+
+use super::{AllTables, BuildConfig, Card, Cards, Threads, Wdl};
+
+// results of one card set, see [Atlas]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasEntry {
+    pub cards: u64,
+    // every state of the tables, including the states where the game is already over
+    pub states: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    // states where the board looks the same for both players, only the cards differ
+    // these tell how much it is worth to be the player to move
+    pub symmetric: u64,
+    pub symmetric_wins: u64,
+    pub symmetric_losses: u64,
+    // longest distance to conversion of a won state in plies
+    // only known if the atlas stores distances
+    pub longest_dtc: Option<u8>,
+    // the mirror of this card set, if the entry was copied from it
+    pub mirror_of: Option<u64>,
+    pub elapsed: Duration,
+}
+
+impl AtlasEntry {
+    pub fn draw_rate(&self) -> f64 {
+        self.draws as f64 / self.states as f64
+    }
+
+    // the part of the symmetric states that the player to move wins minus the part they lose
+    pub fn first_player_advantage(&self) -> f64 {
+        (self.symmetric_wins as f64 - self.symmetric_losses as f64) / self.symmetric as f64
+    }
+}
+
+// builds the tables for many card sets and keeps only their statistics
+// a card set and its mirror have the same results, so only one of them is built
+pub struct Atlas {
+    size: u32,
+    dtc: bool,
+    threads: Threads,
+    entries: Vec<AtlasEntry>,
+    built: HashMap<u64, usize>,
+}
+
+impl Atlas {
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            dtc: false,
+            threads: Threads::Count(1),
+            entries: vec![],
+            built: HashMap::new(),
+        }
+    }
+
+    // also store the distances while building, this is needed for [AtlasEntry::longest_dtc]
+    pub fn dtc(mut self, dtc: bool) -> Self {
+        self.dtc = dtc;
+        self
+    }
+
+    // the threads used for every build, see [BuildConfig::threads]
+    pub fn threads(mut self, threads: Threads) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn entries(&self) -> &[AtlasEntry] {
+        &self.entries
+    }
+
+    pub fn add(&mut self, cards: u64) -> AtlasEntry {
+        let mirror = Cards(cards).mirror().0;
+        let entry = match self.built.get(&mirror) {
+            Some(&i) => AtlasEntry {
+                cards,
+                mirror_of: Some(mirror),
+                elapsed: Duration::ZERO,
+                ..self.entries[i]
+            },
+            None => self.build(cards),
+        };
+        self.built.insert(cards, self.entries.len());
+        self.entries.push(entry);
+        entry
+    }
+
+    fn build(&self, cards: u64) -> AtlasEntry {
+        let start = Instant::now();
+        let tb = BuildConfig::new(self.size, cards)
+            .wdl(true)
+            .dtc(self.dtc)
+            .threads(self.threads)
+            .observer(|_| {})
+            .build();
+        let states = tb.len() * 30;
+        let wins = tb.count_ones();
+        let losses = tb.count_losses();
+
+        let (mut symmetric, mut symmetric_wins, mut symmetric_losses) = (0, 0, 0);
+        for table in tb.list.iter() {
+            let counts = table.counts;
+            if counts.count0 != counts.count1 {
+                continue;
+            }
+            for layout in counts {
+                let inv = layout.invert();
+                if (inv.pieces0, inv.pieces1) != (layout.pieces0, layout.pieces1) {
+                    continue;
+                }
+                layout.indexer(counts).for_each(|kpos| {
+                    if kpos.king0 != 24 - kpos.king1 {
+                        return;
+                    }
+                    for bit in 0..30 {
+                        let pos = tb.position(layout, *kpos, bit);
+                        if pos.winner().is_some() {
+                            continue;
+                        }
+                        symmetric += 1;
+                        match tb.probe_wdl(&pos) {
+                            Some(Wdl::Win) => symmetric_wins += 1,
+                            Some(Wdl::Loss) => symmetric_losses += 1,
+                            _ => {}
+                        }
+                    }
+                });
+            }
+        }
+
+        AtlasEntry {
+            cards,
+            states,
+            wins,
+            losses,
+            draws: states - wins - losses,
+            symmetric,
+            symmetric_wins,
+            symmetric_losses,
+            longest_dtc: tb.has_dtc().then(|| longest_dtc(&tb)),
+            mirror_of: None,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    // one row per card set, the cards are written as their names
+    pub fn write_csv(&self, mut w: impl io::Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "size,cards,states,wins,losses,draws,draw_rate,symmetric,symmetric_wins,symmetric_losses,first_player_advantage,longest_dtc,mirror_of,seconds"
+        )?;
+        let names = |cards: u64| {
+            let names: Vec<&str> = Cards(cards).iter().map(Card::name).collect();
+            names.join(" ")
+        };
+        for entry in &self.entries {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                self.size,
+                names(entry.cards),
+                entry.states,
+                entry.wins,
+                entry.losses,
+                entry.draws,
+                entry.draw_rate(),
+                entry.symmetric,
+                entry.symmetric_wins,
+                entry.symmetric_losses,
+                entry.first_player_advantage(),
+                entry.longest_dtc.map_or(String::new(), |x| x.to_string()),
+                entry.mirror_of.map_or(String::new(), names),
+                entry.elapsed.as_secs_f64(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// wins have an odd distance
+fn longest_dtc(tb: &AllTables) -> u8 {
+    let dtc = tb.list.iter().flat_map(|table| table.dtc.iter());
+    dtc.map(|x| x.load(Ordering::Relaxed))
+        .filter(|x| x % 2 == 1)
+        .max()
+        .unwrap_or(0)
+}
+
+// every set of 5 cards from the 16 cards of the base game
+pub fn base_card_sets() -> impl Iterator<Item = u64> {
+    (0..1u64 << 16).filter(|cards| cards.count_ones() == 5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base_card_sets, Atlas};
+
+    #[test]
+    fn atlas() {
+        assert_eq!(base_card_sets().count(), 4368);
+
+        let mut atlas = Atlas::new(2).dtc(true);
+        // ox, boar, horse, elephant and crab are their own mirror
+        let entry = atlas.add(0b11111);
+        assert_eq!(entry.wins, 6752579);
+        assert_eq!(entry.mirror_of, None);
+        assert!(entry.symmetric > 0);
+        assert!(entry.longest_dtc.unwrap() % 2 == 1);
+        assert!((0.0..=1.0).contains(&entry.draw_rate()));
+
+        // frog and rabbit are mirrored
+        let frog = 1 << 10 | 0b1111;
+        let rabbit = 1 << 11 | 0b1111;
+        let entry = atlas.add(frog);
+        let mirror = atlas.add(rabbit);
+        assert_eq!(mirror.mirror_of, Some(frog));
+        assert_eq!(mirror.wins, entry.wins);
+        assert_eq!(mirror.symmetric_wins, entry.symmetric_wins);
+        assert_eq!(
+            mirror.wins,
+            Atlas::new(2).build(rabbit).wins,
+            "the mirror has the same results"
+        );
+
+        let mut csv = vec![];
+        atlas.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.contains(",Ox Boar Horse Elephant Crab,"));
+    }
+}
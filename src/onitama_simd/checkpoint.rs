@@ -0,0 +1,226 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Write},
+    path::Path,
+    time::Duration,
+};
+
+use super::{
+    store::{invalid, read_u32, read_u64},
+    AllTables, PawnCount, TableStats,
+};
+
+// progress file layout, all integers are little endian:
+// MAGIC, VERSION: u32, wdl: u32, dtc: u32, size: u32, cards: u64, entries: u32
+// then num_stats: u32 and the [TableStats] of every finished table as
+// count0, count1: u32, wins, win_in1, unresolved: u64, iterations: u32, elapsed in nanoseconds: u64
+// and the layouts resolved before and after going up and the skipped blocks as u64
+// followed by the version of every table in `count_indexer` order as u32
+// version `v` of a table is stored next to it in `{count0}_{count1}_{v}.table`
+// in the format of [Table::write_to], version 0 means that the table is still empty
+const MAGIC: &[u8; 8] = b"ONICKPT\0";
+const VERSION: u32 = 5;
+const PROGRESS: &str = "progress";
+
+// the state of a build after some entries of the schedule are finished
+// tables of later entries can already contain wins that lead to taking a piece
+#[derive(Debug)]
+pub(crate) struct Progress {
+    pub entries: usize,
+    pub tables: Vec<TableStats>,
+    versions: Vec<u32>,
+}
+
+impl Progress {
+    pub fn new(num_tables: usize) -> Self {
+        Self {
+            entries: 0,
+            tables: vec![],
+            versions: vec![0; num_tables],
+        }
+    }
+}
+
+impl AllTables {
+    // stores the tables that were changed by the last entries of the schedule and the progress
+    // the tables get a new version and the progress is written last,
+    // so an interrupted checkpoint does not change the files of the previous checkpoint
+    pub(crate) fn write_checkpoint(
+        &self,
+        dir: &Path,
+        written: &[usize],
+        progress: &mut Progress,
+    ) -> io::Result<()> {
+        let version = progress.entries as u32;
+        let mut old = vec![];
+        for &i in written {
+            let table = &self.list[i];
+            let path = dir.join(table_name(table.counts, version));
+            write_atomic(&path, |w| table.write_to(w))?;
+            if progress.versions[i] != 0 {
+                old.push(dir.join(table_name(table.counts, progress.versions[i])));
+            }
+            progress.versions[i] = version;
+        }
+
+        write_atomic(&dir.join(PROGRESS), |w| {
+            w.write_all(MAGIC)?;
+            w.write_all(&VERSION.to_le_bytes())?;
+            w.write_all(&(self.wdl as u32).to_le_bytes())?;
+            w.write_all(&(self.dtc as u32).to_le_bytes())?;
+            w.write_all(&self.size.to_le_bytes())?;
+            w.write_all(&self.cards.0.to_le_bytes())?;
+            w.write_all(&(progress.entries as u32).to_le_bytes())?;
+            w.write_all(&(progress.tables.len() as u32).to_le_bytes())?;
+            for stats in &progress.tables {
+                write_stats(w, stats)?;
+            }
+            for version in &progress.versions {
+                w.write_all(&version.to_le_bytes())?;
+            }
+            Ok(())
+        })?;
+
+        // the old versions are not needed anymore
+        for path in old {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    // loads the tables that were stored by an earlier build
+    // returns an empty progress if there is no checkpoint in `dir`
+    pub(crate) fn read_checkpoint(&self, dir: &Path, num_entries: usize) -> io::Result<Progress> {
+        let mut progress = Progress::new(self.list.len());
+        let file = match File::open(dir.join(PROGRESS)) {
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(progress),
+            file => file?,
+        };
+        let mut r = BufReader::new(file);
+        let mut magic = [0; 8];
+        io::Read::read_exact(&mut r, &mut magic)?;
+        if &magic != MAGIC || read_u32(&mut r)? != VERSION {
+            return Err(invalid("not a checkpoint of this version"));
+        }
+        let wdl = read_u32(&mut r)? != 0;
+        let dtc = read_u32(&mut r)? != 0;
+        let size = read_u32(&mut r)?;
+        let cards = read_u64(&mut r)?;
+        if (wdl, dtc, size, cards) != (self.wdl, self.dtc, self.size, self.cards.0) {
+            return Err(invalid("the checkpoint is for different settings"));
+        }
+        progress.entries = read_u32(&mut r)? as usize;
+        if progress.entries > num_entries {
+            return Err(invalid("the checkpoint has too many entries"));
+        }
+        let num_stats = read_u32(&mut r)?;
+        if num_stats as usize > self.list.len() {
+            return Err(invalid("the checkpoint has too many tables"));
+        }
+        for _ in 0..num_stats {
+            progress.tables.push(read_stats(&mut r)?);
+        }
+
+        for (table, version) in self.list.iter().zip(&mut progress.versions) {
+            *version = read_u32(&mut r)?;
+            if *version == 0 {
+                continue;
+            }
+            let file = File::open(dir.join(table_name(table.counts, *version)))?;
+            table.read_from(&mut BufReader::new(file))?;
+        }
+        Ok(progress)
+    }
+}
+
+fn write_stats(w: &mut impl Write, stats: &TableStats) -> io::Result<()> {
+    w.write_all(&stats.counts.count0.to_le_bytes())?;
+    w.write_all(&stats.counts.count1.to_le_bytes())?;
+    w.write_all(&stats.wins.to_le_bytes())?;
+    w.write_all(&stats.win_in1.to_le_bytes())?;
+    w.write_all(&stats.unresolved.to_le_bytes())?;
+    w.write_all(&(stats.iterations as u32).to_le_bytes())?;
+    w.write_all(&(stats.elapsed.as_nanos() as u64).to_le_bytes())?;
+    w.write_all(&stats.resolved[0].to_le_bytes())?;
+    w.write_all(&stats.resolved[1].to_le_bytes())?;
+    w.write_all(&stats.skipped.to_le_bytes())
+}
+
+fn read_stats(r: &mut impl io::Read) -> io::Result<TableStats> {
+    Ok(TableStats {
+        counts: PawnCount {
+            count0: read_u32(r)?,
+            count1: read_u32(r)?,
+        },
+        wins: read_u64(r)?,
+        win_in1: read_u64(r)?,
+        unresolved: read_u64(r)?,
+        iterations: read_u32(r)? as u16,
+        elapsed: Duration::from_nanos(read_u64(r)?),
+        resolved: [read_u64(r)?, read_u64(r)?],
+        skipped: read_u64(r)?,
+    })
+}
+
+fn table_name(counts: PawnCount, version: u32) -> String {
+    format!("{}_{}_{version}.table", counts.count0, counts.count1)
+}
+
+// the file is replaced at once, so it is never left half written
+fn write_atomic(
+    path: &Path,
+    f: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut w = BufWriter::new(File::create(&tmp)?);
+    f(&mut w)?;
+    w.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::onitama_simd::BuildConfig;
+
+    #[test]
+    fn resume() {
+        let dir = std::env::temp_dir().join(format!("onitama_resume_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let config = BuildConfig::new(2, 0b11111).dtc(true);
+        let full = config.build();
+
+        let checkpointed = config.clone().checkpoint(&dir).build();
+        assert!(checkpointed == full);
+
+        // a finished checkpoint is loaded without building anything
+        let resumed = config.clone().checkpoint(&dir).build();
+        assert!(resumed == full);
+        assert_eq!(resumed.stats().win_in1(), full.stats().win_in1());
+        assert_eq!(resumed.stats().unresolved(), full.stats().unresolved());
+        fs::remove_dir_all(&dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        // interrupt the build after every entry and continue it
+        for entries in 1..=4 {
+            let mut interrupted = config.clone().checkpoint(&dir);
+            interrupted.max_entries = Some(entries);
+            interrupted.build();
+        }
+        let resumed = config.clone().checkpoint(&dir).build();
+        assert!(resumed == full);
+        assert_eq!(resumed.stats().win_in1(), full.stats().win_in1());
+        assert_eq!(resumed.stats().unresolved(), full.stats().unresolved());
+        let files = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 5);
+
+        // a checkpoint can not be used for other settings
+        assert!(BuildConfig::new(2, 0b11111)
+            .checkpoint(&dir)
+            .try_build()
+            .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
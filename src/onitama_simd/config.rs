@@ -0,0 +1,281 @@
+use std::{fmt, io, path::PathBuf, sync::Arc, time::Duration};
+
+use rayon::ThreadPoolBuilder;
+
+use super::{AllTables, PawnCount};
+
+// settings for generating [AllTables]
+#[derive(Debug, Clone)]
+pub struct BuildConfig {
+    pub(crate) size: u32,
+    pub(crate) cards: u64,
+    pub(crate) wdl: bool,
+    pub(crate) dtc: bool,
+    pub(crate) mirror: bool,
+    pub(crate) dir: Option<PathBuf>,
+    pub(crate) checkpoint: Option<PathBuf>,
+    // stops the build after this many entries of the schedule, to test interrupted builds
+    pub(crate) max_entries: Option<usize>,
+    pub(crate) observer: Option<Observer>,
+    pub(crate) threads: Threads,
+    pub(crate) trace: Option<PathBuf>,
+    pub(crate) skip_resolved: bool,
+}
+
+// the size of the tables for a number of pieces on the board, 2, 4, 6, 8 or 10
+// both players have half of the pieces, see [BuildConfig::new]
+pub fn parse_size(pieces: &str) -> Result<u32, String> {
+    match pieces.parse::<u32>() {
+        Ok(pieces @ (2 | 4 | 6 | 8 | 10)) => Ok(pieces / 2),
+        Ok(pieces) => Err(format!("{pieces} pieces are not supported")),
+        Err(_) => Err(format!("expected integer, got {pieces}")),
+    }
+}
+
+// the threads that are used to build the tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threads {
+    // the global rayon pool, it is shared with the rest of the program
+    Global,
+    // a dedicated rayon pool with this many threads
+    // with 1 thread every layout is updated in the same order, so the build is deterministic
+    Count(usize),
+}
+
+// progress of a build, every table goes through these events in order
+// a pawn count and its inverse are solved together, so their events are interleaved
+#[derive(Debug, Clone, Copy)]
+pub enum BuildEvent {
+    TableStart {
+        counts: PawnCount,
+    },
+    // after every iteration over the layouts that are not resolved yet
+    Iteration {
+        counts: PawnCount,
+        iteration: u16,
+        remaining: usize,
+        resolved: usize,
+    },
+    // no progress was made in the last iteration, so wins that lead to taking a piece
+    // are now added to the table with one more pawn
+    GoUp {
+        counts: PawnCount,
+        iteration: u16,
+    },
+    TableDone {
+        counts: PawnCount,
+        iterations: u16,
+        elapsed: Duration,
+    },
+}
+
+#[derive(Clone)]
+pub(crate) struct Observer(Arc<dyn Fn(BuildEvent) + Send + Sync>);
+
+impl Observer {
+    pub(crate) fn notify(&self, event: BuildEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+impl BuildConfig {
+    // tables with up to `size` pieces per player for the given set of 5 cards
+    // bit `i` of `cards` selects the card with index `i`, see [crate::card::Card]
+    pub fn new(size: u32, cards: u64) -> Self {
+        Self {
+            size,
+            cards,
+            wdl: false,
+            dtc: false,
+            mirror: false,
+            dir: None,
+            checkpoint: None,
+            max_entries: None,
+            observer: None,
+            threads: Threads::Count(1),
+            trace: None,
+            skip_resolved: false,
+        }
+    }
+
+    // also store the proven losses, this allows probes to tell draws from losses
+    // it doubles the memory that is used
+    pub fn wdl(mut self, wdl: bool) -> Self {
+        self.wdl = wdl;
+        self
+    }
+
+    // also store the distance to conversion (dtc) of every won or lost state
+    // this implies [BuildConfig::wdl]
+    // it counts the plies until the game ends or a piece is taken, so it is only comparable
+    // between positions with the same number of pieces
+    // it is stored as one byte per state, which is 7.5 times the memory of the tables without it
+    // the build fails if a distance does not fit in a byte
+    pub fn dtc(mut self, dtc: bool) -> Self {
+        self.dtc = dtc;
+        self
+    }
+
+    // only store one of every pair of layouts that are mirrored from left to right
+    // this almost halves the number of stored layouts, probes mirror the position when needed
+    // the stored layouts are found with an index that takes 1.5 bits per layout of the full table
+    // it only works if the mirror of every card is in the set, otherwise the build fails
+    // the tables are reduced after building, so it does not lower the memory used while building
+    pub fn mirror(mut self, mirror: bool) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    // keep the tables in memory mapped files in this directory instead of on the heap
+    // only the tables used by the pawn counts that are being solved are kept in memory
+    // existing table files in the directory are overwritten and they are kept after building
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    // store the finished tables in this directory after every pawn count
+    // a build with the same settings and directory continues after the last stored pawn count
+    // the directory can be the same as the one of [BuildConfig::dir]
+    pub fn checkpoint(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(dir.into());
+        self
+    }
+
+    // called for every [BuildEvent], otherwise only finished tables are printed
+    pub fn observer(mut self, f: impl Fn(BuildEvent) + Send + Sync + 'static) -> Self {
+        self.observer = Some(Observer(Arc::new(f)));
+        self
+    }
+
+    // the build uses 1 thread by default
+    pub fn threads(mut self, threads: Threads) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    // write every iteration to this file, the format is described in [Trace]
+    // the build uses a single thread while tracing, so two traces of the same build are equal
+    // and can be compared to find where a change to the update code makes a difference
+    // the trace contains every changed block, so it is only useful for small sizes
+    pub fn trace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.trace = Some(path.into());
+        self
+    }
+
+    // mark blocks of which all states are won or lost with [RESOLVED_BIT](super::RESOLVED_BIT)
+    // and skip them in later iterations
+    // the tables are the same either way, [TableStats::skipped](super::TableStats::skipped)
+    // tells how many blocks were skipped
+    // this is off by default, because checking the bit costs about as much as it saves
+    pub fn skip_resolved(mut self, skip_resolved: bool) -> Self {
+        self.skip_resolved = skip_resolved;
+        self
+    }
+
+    // panics if the tables can not be built, see [BuildConfig::try_build]
+    pub fn build(&self) -> AllTables {
+        self.try_build().expect("could not build the tables")
+    }
+
+    // fails when `cards` is not a set of 5 cards or can not be mirrored, see [BuildConfig::mirror]
+    // or when the files can not be used, see [BuildConfig::dir] and [BuildConfig::checkpoint]
+    // or when a distance does not fit, see [BuildConfig::dtc]
+    pub fn try_build(&self) -> io::Result<AllTables> {
+        let threads = match self.trace {
+            Some(_) => Threads::Count(1),
+            None => self.threads,
+        };
+        match threads {
+            Threads::Global => AllTables::build_with(self),
+            Threads::Count(n) => ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .map_err(io::Error::other)?
+                .install(|| AllTables::build_with(self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::onitama_simd::AllTables;
+
+    use super::{parse_size, BuildConfig, BuildEvent, Threads};
+
+    #[test]
+    fn observer_events() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let log = events.clone();
+        BuildConfig::new(2, 0b11111)
+            .observer(move |event| log.lock().unwrap().push(event))
+            .build();
+
+        let events = events.lock().unwrap();
+        let starts = events
+            .iter()
+            .filter(|e| matches!(e, BuildEvent::TableStart { .. }));
+        assert_eq!(starts.count(), 4);
+        for counts in events.iter().filter_map(|e| match e {
+            BuildEvent::TableStart { counts } => Some(*counts),
+            _ => None,
+        }) {
+            let table: Vec<_> = events
+                .iter()
+                .filter(|e| match e {
+                    BuildEvent::TableStart { counts: c }
+                    | BuildEvent::Iteration { counts: c, .. }
+                    | BuildEvent::GoUp { counts: c, .. }
+                    | BuildEvent::TableDone { counts: c, .. } => *c == counts,
+                })
+                .collect();
+            assert!(matches!(table[0], BuildEvent::TableStart { .. }));
+            let go_up = table
+                .iter()
+                .filter(|e| matches!(e, BuildEvent::GoUp { .. }));
+            assert_eq!(go_up.count(), 1);
+            let iterations = table
+                .iter()
+                .filter(|e| matches!(e, BuildEvent::Iteration { .. }));
+            let BuildEvent::TableDone { iterations: n, .. } = table[table.len() - 1] else {
+                panic!("the last event should be TableDone");
+            };
+            assert_eq!(iterations.count(), *n as usize);
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("2"), Ok(1));
+        assert_eq!(parse_size("10"), Ok(5));
+        for bad in ["0", "3", "12", "six"] {
+            assert!(parse_size(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn invalid_cards() {
+        for cards in [0b1111, 0b111111, 1 << 34 | 0b1111, 1 << 63 | 0b1111] {
+            let config = BuildConfig::new(1, cards);
+            assert!(config.try_build().is_err(), "{cards:#b}");
+        }
+    }
+
+    #[test]
+    fn threads() {
+        let single = AllTables::build(2, 0b11111);
+        let config = BuildConfig::new(2, 0b11111);
+        for threads in [Threads::Global, Threads::Count(4)] {
+            let tb = config.clone().threads(threads).build();
+            assert!(tb == single);
+        }
+    }
+}
@@ -81,6 +81,12 @@ impl Indexer for PawnCount {
     fn total(&self) -> usize {
         combinations(25, self.count0 as i32 + 1, self.count1 as i32 + 1)
     }
+
+    fn unindex(&self, index: usize) -> Self::Item {
+        let (count0, count1) = (self.count0 as i32 + 1, self.count1 as i32 + 1);
+        let (pieces0, pieces1) = unranking(index, 25, count0, count1);
+        TeamLayout { pieces0, pieces1 }
+    }
 }
 
 pub fn g((mut ones, mut twos): (u32, u32)) -> (u32, u32) {
@@ -296,7 +302,12 @@ mod tests {
             let idx = ranking(layout.pieces0, layout.pieces1);
             // println!("{idx}");
             assert!(set.insert(idx));
-            assert_eq!(unranking(idx, 25, 2, 2), (layout.pieces0, layout.pieces1))
+            assert_eq!(unranking(idx, 25, 2, 2), (layout.pieces0, layout.pieces1));
+            let unindexed = counts.unindex(idx);
+            assert_eq!(
+                (unindexed.pieces0, unindexed.pieces1),
+                (layout.pieces0, layout.pieces1)
+            );
             // assert_eq!(idx, original(layout.pieces0, layout.pieces1))
         }
         // assert_eq!(
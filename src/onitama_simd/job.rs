@@ -1,6 +1,3 @@
-#[cfg(feature = "parallell")]
-use rayon::prelude::ParallelExtend;
-#[cfg(feature = "parallell")]
 use rayon::prelude::*;
 
 use std::{
@@ -9,32 +6,30 @@ use std::{
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
 };
 
+use bit_iter::BitIter;
+
 use crate::{index::Indexer, onitama_simd::LocalMem};
 
-use super::{AllTables, Block, ImmutableUpdate, PawnCount, TableJob, Update};
+use super::{
+    AllTables, Block, ImmutableUpdate, PawnCount, TableJob, TeamLayout, Update, BLOCK_MASK,
+    RESOLVED_BIT,
+};
 
 impl<'a> TableJob<'a> {
-    pub fn new(tb: &'a AllTables, counts: PawnCount) -> Self {
-        let PawnCount { count0, count1 } = counts;
-
+    pub fn new(tb: &'a AllTables, counts: PawnCount, skip_resolved: bool) -> Self {
         let update = ImmutableUpdate {
             current: tb.index_count(counts),
             inv_current: tb.index_count(counts.invert()),
-            take_one: (count1 != 0).then(|| {
-                tb.index_count(PawnCount {
-                    count0,
-                    count1: count1 - 1,
-                })
-            }),
-            leave_one: (count0 + 1 != tb.size).then(|| {
-                tb.index_count(PawnCount {
-                    count0: count0 + 1,
-                    count1,
-                })
-            }),
+            take_one: counts.take_one().map(|counts| tb.index_count(counts)),
+            leave_one: counts
+                .leave_one(tb.size)
+                .map(|counts| tb.index_count(counts)),
             go_up: false,
             mask_lookup: &tb.mask_lookup,
             directions: tb.directions,
+            dtc: tb.dtc,
+            skip_resolved,
+            iteration: 0,
         };
 
         let mut layouts = Vec::with_capacity(counts.total());
@@ -45,16 +40,20 @@ impl<'a> TableJob<'a> {
             resolved: Vec::with_capacity(counts.total()),
             update,
             done: false,
+            progress: false,
+            resolved_per_phase: [0; 2],
+            skipped: 0,
+            overflow: false,
             total_unresolved: AtomicU64::new(0),
             tb,
         }
     }
 
     pub fn count_unresolved(&self) {
-        #[cfg(feature = "parallell")]
-        let iter = self.layouts.par_iter();
-        #[cfg(not(feature = "parallell"))]
-        let iter = self.layouts.iter();
+        // the losses are also needed for the layouts that are already resolved
+        let resolved: &[TeamLayout] = if self.tb.wdl { &self.resolved } else { &[] };
+
+        let iter = self.layouts.par_iter().chain(resolved.par_iter());
 
         iter.for_each(|layout| {
             UPDATE.with(|vals| {
@@ -64,9 +63,12 @@ impl<'a> TableJob<'a> {
                     immutable: &self.update,
                     mem,
                 };
-                let unresolved = update.get_unresolved::<true>();
+                let unresolved = update.get_unresolved::<true, false>();
                 self.total_unresolved
                     .fetch_add(unresolved, Ordering::Relaxed);
+                if self.tb.wdl {
+                    update.store_losses();
+                }
             })
         });
     }
@@ -74,10 +76,7 @@ impl<'a> TableJob<'a> {
     pub fn mark_ez_win(&self) {
         let counts = self.update.current.counts;
 
-        #[cfg(feature = "parallell")]
         let iter = self.layouts.par_iter();
-        #[cfg(not(feature = "parallell"))]
-        let iter = self.layouts.iter();
 
         iter.for_each(|layout| {
             self.tb.ez_win_for_each(counts, *layout, &mut |i, mask| {
@@ -88,10 +87,58 @@ impl<'a> TableJob<'a> {
             })
         });
     }
+
+    // all wins that are known before the first iteration are won in one move
+    // or by taking a piece, which moves to a smaller table
+    pub fn mark_initial_dtc(&self) {
+        let table = self.update.current;
+
+        let iter = table.list.par_iter().enumerate();
+
+        iter.for_each(|(i, val)| {
+            let wins = val.load(Ordering::Relaxed) & BLOCK_MASK;
+            for bit in BitIter::from(wins) {
+                table.dtc(i, bit as u32).store(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    // the RESOLVED_BIT is only used while solving, so it is removed from the finished table
+    pub fn clear_resolved(&self) {
+        let iter = self.update.current.list.par_iter();
+
+        iter.for_each(|val| {
+            if val.load(Ordering::Relaxed) & RESOLVED_BIT != 0 {
+                val.fetch_and(!RESOLVED_BIT, Ordering::Relaxed);
+            }
+        });
+    }
+
+    // adds the wins of the last iteration to the table and stores their distance
+    pub fn merge_pending(&self, dist: u8) {
+        let table = self.update.current;
+
+        let iter = table
+            .pending
+            .par_iter()
+            .zip(table.list.par_iter())
+            .enumerate();
+
+        iter.for_each(|(i, (pending, val))| {
+            if pending.load(Ordering::Relaxed) == 0 {
+                return;
+            }
+            let wins = pending.swap(0, Ordering::Relaxed);
+            let new = wins & !val.fetch_or(wins, Ordering::Relaxed);
+            for bit in BitIter::from(new & BLOCK_MASK) {
+                table.dtc(i, bit as u32).store(dist, Ordering::Relaxed);
+            }
+        });
+    }
 }
 
 thread_local! {
-    static UPDATE: RefCell<LocalMem> = RefCell::new(LocalMem::new());
+    static UPDATE: RefCell<LocalMem> = const { RefCell::new(LocalMem::new()) };
 }
 
 impl Iterator for TableJob<'_> {
@@ -101,12 +148,11 @@ impl Iterator for TableJob<'_> {
         if self.done {
             return None;
         };
-        #[cfg(feature = "parallell")]
         let iter = self.layouts.par_iter();
-        #[cfg(not(feature = "parallell"))]
-        let iter = self.layouts.iter();
 
         let progress = AtomicBool::new(false);
+        let skipped = AtomicU64::new(0);
+        let overflow = AtomicBool::new(false);
         let iter = iter.map(|layout| {
             UPDATE.with(|vals| {
                 let mem = &mut *vals.borrow_mut();
@@ -117,14 +163,13 @@ impl Iterator for TableJob<'_> {
                 };
                 let tmp = update.update_layout();
                 progress.fetch_or(tmp.progress, Ordering::Relaxed);
+                skipped.fetch_add(tmp.skipped, Ordering::Relaxed);
+                overflow.fetch_or(tmp.overflow, Ordering::Relaxed);
                 tmp.unresolved == 0
             })
         });
 
-        #[cfg(feature = "parallell")]
         self.is_resolved.par_extend(iter);
-        #[cfg(not(feature = "parallell"))]
-        self.is_resolved.extend(iter);
 
         let mut i = 0;
         self.layouts.retain(|layout| {
@@ -135,14 +180,20 @@ impl Iterator for TableJob<'_> {
             i += 1;
             !res
         });
+        let resolved = self.is_resolved.iter().filter(|&&res| res).count();
+        self.resolved_per_phase[self.update.go_up as usize] += resolved as u64;
         self.is_resolved.clear();
 
+        self.skipped += skipped.load(Ordering::Relaxed);
+        self.progress = progress.load(Ordering::Relaxed);
+        self.overflow |= overflow.load(Ordering::Relaxed);
         if self.update.go_up {
             self.done = true;
-        } else if !progress.load(Ordering::Relaxed) {
+        } else if !self.progress {
             self.layouts.extend(take(&mut self.resolved));
             self.update.go_up = true;
         }
+        self.update.iteration += 1;
         Some(())
     }
 }
@@ -0,0 +1,225 @@
+use bit_iter::BitIter;
+
+use crate::{card::mirror_board, index::Indexer};
+
+use super::{AllTables, Card, Cards, KingPos, PawnCount, Position, Storage, Table, TeamLayout};
+
+impl TeamLayout {
+    pub(crate) fn mirror(self) -> Self {
+        TeamLayout {
+            pieces0: mirror_board(self.pieces0),
+            pieces1: mirror_board(self.pieces1),
+        }
+    }
+
+    // only one layout of every mirrored pair is canonical, the one with the lowest index
+    pub(crate) fn is_canonical(self, counts: PawnCount) -> bool {
+        counts.index(&self) <= counts.index(&self.mirror())
+    }
+}
+
+impl KingPos {
+    pub(crate) fn mirror(self) -> Self {
+        Self {
+            king0: mirror_square(self.king0),
+            king1: mirror_square(self.king1),
+        }
+    }
+}
+
+fn mirror_square(square: u32) -> u32 {
+    square - square % 5 + 4 - square % 5
+}
+
+impl Cards {
+    // whether the mirror of every card is also part of the set
+    pub(crate) fn is_symmetric(self) -> bool {
+        self.iter()
+            .all(|card| self.position(card.mirror()).is_some())
+    }
+
+    // the set with every card replaced by its mirror, it has the same results
+    pub(crate) fn mirror(self) -> Cards {
+        Cards(self.iter().map(|card| 1 << card.mirror().index()).sum())
+    }
+}
+
+impl Position {
+    // the same position flipped from left to right, the cards are replaced by their mirror
+    // both positions have the same outcome
+    pub fn mirror(&self) -> Position {
+        Position {
+            pieces: self.pieces.map(mirror_board),
+            kings: self.kings.map(mirror_square),
+            hands: self.hands.map(|hand| hand.map(Card::mirror)),
+            side: self.side.mirror(),
+            to_move: self.to_move,
+        }
+    }
+}
+
+impl AllTables {
+    // whether only the canonical layouts are stored, see [BuildConfig::mirror]
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    // drops the layouts that are the mirror of another layout
+    // the card set has to be symmetric, otherwise mirrored positions use cards
+    // that are not part of the tables
+    pub(crate) fn mirror(&mut self) {
+        debug_assert!(self.cards.is_symmetric());
+        for table in self.list.iter_mut() {
+            table.mirror();
+        }
+        self.mirrored = true;
+    }
+}
+
+impl Table {
+    pub(super) fn mirror(&mut self) {
+        let canonical = Canonical::new(self.counts);
+
+        let chunk_size = self.chunk_size;
+        compact(&mut self.list, chunk_size, &canonical);
+        compact(&mut self.losses, chunk_size, &canonical);
+        compact(&mut self.dtc, chunk_size * 30, &canonical);
+        // only needed while building
+        self.pending = Storage::default();
+        self.canonical = Some(canonical);
+    }
+}
+
+// the layouts of a table that are stored when it is reduced by mirror symmetry
+// bit `i` of `bits` is set if the layout with index `i` is canonical
+// and `ranks` counts the canonical layouts before every word of `bits`
+// so the position of a layout in the table is found without searching
+// this takes 1.5 bits per layout of the full table
+#[derive(Debug)]
+pub(crate) struct Canonical {
+    bits: Box<[u64]>,
+    ranks: Box<[u32]>,
+}
+
+impl Canonical {
+    fn new(counts: PawnCount) -> Self {
+        let mut bits = vec![0u64; counts.total().div_ceil(64)];
+        for layout in counts {
+            if layout.is_canonical(counts) {
+                let i = counts.index(&layout);
+                bits[i / 64] |= 1 << (i % 64);
+            }
+        }
+        let mut rank = 0;
+        let ranks = bits
+            .iter()
+            .map(|word| {
+                let start = rank;
+                rank += word.count_ones();
+                start
+            })
+            .collect();
+        Self {
+            bits: bits.into(),
+            ranks,
+        }
+    }
+
+    // position in the table of the canonical layout with index `i`
+    pub(crate) fn rank(&self, i: usize) -> usize {
+        let word = self.bits[i / 64];
+        assert!(
+            word & 1 << (i % 64) != 0,
+            "only canonical layouts are stored"
+        );
+        let below = word & ((1 << (i % 64)) - 1);
+        self.ranks[i / 64] as usize + below.count_ones() as usize
+    }
+
+    // index of the layout at position `rank` of the table, the inverse of [Canonical::rank]
+    pub(crate) fn select(&self, rank: usize) -> usize {
+        let i = self.ranks.partition_point(|&start| start as usize <= rank) - 1;
+        let mut word = self.bits[i];
+        for _ in self.ranks[i] as usize..rank {
+            word &= word - 1;
+        }
+        i * 64 + word.trailing_zeros() as usize
+    }
+
+    // the indices of the canonical layouts in increasing order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let words = self.bits.iter().enumerate();
+        words.flat_map(|(i, &word)| BitIter::from(word).map(move |bit| i * 64 + bit))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.bits.len() * 8 + self.ranks.len() * 4
+    }
+}
+
+// moves the chunks of the canonical layouts to the front and drops the rest
+// chunks only move to lower positions, because the canonical layouts are in increasing order
+fn compact<T>(list: &mut Storage<T>, chunk_size: usize, canonical: &Canonical) {
+    if list.is_empty() {
+        return;
+    }
+    for (j, i) in canonical.iter().enumerate() {
+        let (i, j) = (i * chunk_size, j * chunk_size);
+        for k in 0..chunk_size {
+            list.swap(j + k, i + k);
+        }
+    }
+    list.truncate(canonical.len() * chunk_size);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onitama_simd::{BuildConfig, PawnCount};
+
+    #[test]
+    fn mirrored_probes() {
+        let full = BuildConfig::new(2, 0b11111).dtc(true).build();
+        let tb = BuildConfig::new(2, 0b11111).dtc(true).mirror(true).build();
+        assert!(tb.is_mirrored());
+        assert_eq!(tb.count_ones(), full.count_ones());
+        assert_eq!(tb.count_losses(), full.count_losses());
+
+        // the wins of every table together with the index of its canonical layouts
+        let stored = |tb: &super::AllTables| {
+            let table = |t: &super::Table| {
+                let index = t.canonical.as_ref().map_or(0, |c| c.size_in_bytes());
+                t.list.len() * 4 + index
+            };
+            tb.list.iter().map(table).sum::<usize>()
+        };
+        assert!(stored(&tb) * 10 < stored(&full) * 6);
+        for table in tb.list.iter() {
+            let canonical = table.canonical.as_ref().unwrap();
+            for (rank, i) in canonical.iter().enumerate() {
+                assert_eq!(canonical.rank(i), rank);
+                assert_eq!(canonical.select(rank), i);
+            }
+        }
+
+        let counts = PawnCount {
+            count0: 1,
+            count1: 0,
+        };
+        full.for_each_state(counts, |pos, _, _, _| {
+            assert_eq!(tb.probe_wdl(&pos), full.probe_wdl(&pos));
+            assert_eq!(tb.probe_dtc(&pos), full.probe_dtc(&pos));
+            assert_eq!(full.probe_dtc(&pos.mirror()), full.probe_dtc(&pos));
+        });
+
+        // the frog has no mirror in this set
+        let config = BuildConfig::new(1, 1 << 10 | 0b1111).mirror(true);
+        assert!(config.try_build().is_err());
+    }
+}
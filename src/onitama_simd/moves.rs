@@ -0,0 +1,309 @@
+use std::collections::HashSet;
+
+use bit_iter::BitIter;
+
+use crate::card::offset_mask_fixed as offset_mask;
+
+use super::{AllTables, Card, Position, Wdl};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub card: Card,
+    pub from: u32,
+    pub to: u32,
+}
+
+impl Position {
+    // the player that won, the game is over when this is `Some`
+    pub fn winner(&self) -> Option<usize> {
+        for player in 0..2 {
+            let other = 1 - player;
+            if self.pieces[other] & 1 << self.kings[other] == 0 {
+                return Some(player);
+            }
+        }
+        if self.kings[0] == 22 {
+            Some(0)
+        } else if self.kings[1] == 2 {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    // all legal moves of the player to move
+    pub fn moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        if self.winner().is_some() {
+            return moves;
+        }
+
+        let own = self.pieces[self.to_move];
+        for card in self.hands[self.to_move] {
+            // player 1 sees the cards upside down
+            let bitmap = if self.to_move == 0 {
+                card.bitmap::<false>()
+            } else {
+                card.bitmap::<true>()
+            };
+            for from in BitIter::from(own) {
+                // can not move onto your own pieces
+                let to_mask = offset_mask(from, bitmap) & !own;
+                for to in BitIter::from(to_mask) {
+                    moves.push(Move {
+                        card,
+                        from: from as u32,
+                        to: to as u32,
+                    })
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn play(&self, mv: Move) -> Position {
+        let player = self.to_move;
+        let other = 1 - player;
+        debug_assert_ne!(self.pieces[player] & 1 << mv.from, 0);
+        debug_assert!(self.hands[player].contains(&mv.card));
+
+        let mut next = *self;
+        next.pieces[player] ^= 1 << mv.from | 1 << mv.to;
+        next.pieces[other] &= !(1 << mv.to);
+        if self.kings[player] == mv.from {
+            next.kings[player] = mv.to;
+        }
+        // the used card is exchanged with the side card
+        for card in &mut next.hands[player] {
+            if *card == mv.card {
+                *card = self.side;
+            }
+        }
+        next.side = mv.card;
+        next.to_move = other;
+        next
+    }
+
+    // whether the move takes a piece that is not the king
+    fn is_take(&self, mv: Move) -> bool {
+        let other = 1 - self.to_move;
+        self.pieces[other] & 1 << mv.to != 0 && self.kings[other] != mv.to
+    }
+}
+
+impl AllTables {
+    // all moves that win immediately or lead to a position that is lost for the opponent
+    // returns `None` if the position is not part of the tables
+    pub fn best_moves(&self, pos: &Position) -> Option<Vec<Move>> {
+        self.probe(pos)?;
+        let mut best = vec![];
+        for mv in pos.moves() {
+            let next = pos.play(mv);
+            if next.winner().is_some() || self.probe_wdl(&next)? == Wdl::Loss {
+                best.push(mv)
+            }
+        }
+        Some(best)
+    }
+
+    // every legal move with its result for the player that makes it
+    // the result is `None` if the position after the move is not part of the tables
+    pub fn move_results(&self, pos: &Position) -> Vec<(Move, Option<Wdl>)> {
+        let result = |mv: Move| {
+            let next = pos.play(mv);
+            if next.winner().is_some() {
+                return Some(Wdl::Win);
+            }
+            Some(match self.probe_wdl(&next)? {
+                Wdl::Win => Wdl::Loss,
+                Wdl::Draw => Wdl::Draw,
+                Wdl::Loss => Wdl::Win,
+            })
+        };
+        pos.moves().into_iter().map(|mv| (mv, result(mv))).collect()
+    }
+
+    // the plies until the game ends or a piece is taken when `mv` is played in `pos`
+    // a move that takes a piece converts at once, the distance of the position after it
+    // only counts to the next conversion
+    // it is only meaningful if the move does not lead to a draw
+    // returns `None` if the position after the move is not part of the tables
+    // or if the tables were built without distances
+    pub fn move_dtc(&self, pos: &Position, mv: Move) -> Option<u16> {
+        let next = pos.play(mv);
+        if next.winner().is_some() || pos.is_take(mv) {
+            return self.dtc.then_some(1);
+        }
+        self.probe_dtc(&next).map(|dtc| dtc as u16 + 1)
+    }
+
+    // the move that keeps the best result for the player to move
+    // a win with the shortest distance to conversion, a loss with the longest
+    // or a move that keeps the draw
+    // every conversion lowers the number of pieces, so the winner always finishes the game
+    // the distances are only used if they are stored
+    // returns `None` if the game is over, there are no moves or the position is not part of the tables
+    pub fn best_move(&self, pos: &Position) -> Option<Move> {
+        if pos.winner().is_some() {
+            return None;
+        }
+        match self.probe_wdl(pos)? {
+            Wdl::Win => {
+                // winning immediately is best, after that taking a piece, which converts at once
+                // and after that the move with the shortest distance to conversion
+                let moves = self.best_moves(pos)?;
+                let key = |mv: &Move| {
+                    let next = pos.play(*mv);
+                    if next.winner().is_some() {
+                        0
+                    } else if pos.is_take(*mv) {
+                        1
+                    } else {
+                        self.probe_dtc(&next).unwrap_or(u8::MAX)
+                    }
+                };
+                moves.into_iter().min_by_key(key)
+            }
+            Wdl::Loss => {
+                // every move leads to a win of the opponent, taking a piece converts at once
+                // so any other move puts off the conversion for longer
+                let key = |mv: &Move| {
+                    if pos.is_take(*mv) {
+                        0
+                    } else {
+                        self.probe_dtc(&pos.play(*mv)).unwrap_or(0)
+                    }
+                };
+                pos.moves().into_iter().max_by_key(key)
+            }
+            Wdl::Draw => pos.moves().into_iter().find(|mv| {
+                let next = pos.play(*mv);
+                next.winner().is_none() && self.probe_wdl(&next) == Some(Wdl::Draw)
+            }),
+        }
+    }
+
+    // the moves of both players until the game ends, starting with a winning move
+    // both players follow the distance to conversion if it is stored, see [AllTables::best_move]
+    // otherwise the line stops when a position repeats
+    // the line is empty for draws
+    // returns `None` if the position is not part of the tables
+    pub fn principal_variation(&self, pos: &Position) -> Option<Vec<Move>> {
+        let mut pos = *pos;
+        let mut line = vec![];
+        let mut seen = HashSet::new();
+        loop {
+            if pos.winner().is_some() || !seen.insert(pos) {
+                return Some(line);
+            }
+            if self.probe_wdl(&pos)? == Wdl::Draw {
+                return Some(line);
+            }
+            let Some(mv) = self.best_move(&pos) else {
+                return Some(line);
+            };
+            line.push(mv);
+            pos = pos.play(mv);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onitama_simd::{AllTables, BuildConfig, PawnCount, Wdl};
+
+    #[test]
+    fn moves_lead_to_tables() {
+        let tb = AllTables::build(2, 0b11111);
+        let counts = PawnCount {
+            count0: 1,
+            count1: 0,
+        };
+        tb.for_each_state(counts, |pos, _, _, _| {
+            let win = tb.probe(&pos).unwrap();
+            let best = tb.best_moves(&pos).unwrap();
+            assert_eq!(win, !best.is_empty());
+            for mv in pos.moves() {
+                let next = pos.play(mv);
+                assert_eq!(next.pieces[0] & next.pieces[1], 0);
+                assert!(next.winner().is_some() || tb.probe(&next).is_some());
+            }
+        });
+    }
+
+    #[test]
+    fn best_move_keeps_result() {
+        let tb = BuildConfig::new(2, 0b11111).wdl(true).build();
+        let counts = PawnCount {
+            count0: 0,
+            count1: 1,
+        };
+        tb.for_each_state(counts, |pos, _, _, _| {
+            let wdl = tb.probe_wdl(&pos).unwrap();
+            let Some(mv) = tb.best_move(&pos) else {
+                assert!(wdl == Wdl::Loss && pos.moves().is_empty());
+                return;
+            };
+            let results = tb.move_results(&pos);
+            let result = results.iter().find(|&&(m, _)| m == mv).unwrap().1;
+            assert_eq!(result, Some(wdl));
+            // no other move is better
+            let order = |wdl: Wdl| {
+                [Wdl::Loss, Wdl::Draw, Wdl::Win]
+                    .iter()
+                    .position(|&w| w == wdl)
+            };
+            for (_, other) in results {
+                assert!(order(other.unwrap()) <= order(wdl));
+            }
+        });
+    }
+
+    #[test]
+    fn move_dtc() {
+        let tb = BuildConfig::new(2, 0b11111).dtc(true).build();
+        let counts = PawnCount {
+            count0: 1,
+            count1: 1,
+        };
+        let (mut takes, mut quiet) = (0, 0);
+        tb.for_each_state(counts, |pos, _, _, _| {
+            for mv in pos.moves() {
+                let next = pos.play(mv);
+                if next.winner().is_some() {
+                    continue;
+                }
+                let dtc = tb.move_dtc(&pos, mv).unwrap();
+                if pos.is_take(mv) {
+                    // the position after the take counts to the next conversion
+                    assert_eq!(dtc, 1);
+                    takes += 1;
+                } else {
+                    assert_eq!(dtc, tb.probe_dtc(&next).unwrap() as u16 + 1);
+                    quiet += 1;
+                }
+            }
+        });
+        assert!(takes > 0 && quiet > 0);
+    }
+
+    #[test]
+    fn pv_length_is_dtc() {
+        // without pawns the distance is exact, because nothing can be taken
+        let tb = BuildConfig::new(1, 0b11111).dtc(true).build();
+        let counts = PawnCount::default();
+        tb.for_each_state(counts, |pos, _, _, _| {
+            let dtc = tb.probe_dtc(&pos).unwrap();
+            let line = tb.principal_variation(&pos).unwrap();
+            assert_eq!(line.len(), dtc as usize);
+            if let Some(mv) = line.last() {
+                let mut end = pos;
+                for mv in &line {
+                    end = end.play(*mv);
+                }
+                assert_eq!(end.winner(), Some(1 - end.to_move));
+                assert_eq!(end.side, mv.card);
+            }
+        });
+    }
+}
@@ -0,0 +1,305 @@
+use std::{fmt, str::FromStr};
+
+use super::{Card, Move, Position};
+
+// a position is written as 5 fields separated by spaces, like
+// `1xXx1/5/5/5/2O2 Ox,Boar Horse,Crab Elephant x`
+// the board has 5 rows separated by `/`, in the same order as [Position::pretty]
+// so the first row is the back row of player 1, square 24 on the left down to square 20
+// `O` and `X` are the kings of player 0 and 1, `o` and `x` their pawns, digits count empty squares
+// then the hand of player 0, the hand of player 1, the side card
+// and the player to move as `o` for player 0 or `x` for player 1
+// card names with a space are written with a dash, like `Sea-Snake`
+// a king that was taken is not written, so the position after the game ended can not be read back
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..5 {
+            if y != 0 {
+                write!(f, "/")?;
+            }
+            let mut empty = 0;
+            for x in 0..5 {
+                let Some(piece) = self.square(24 - 5 * y - x) else {
+                    empty += 1;
+                    continue;
+                };
+                if empty != 0 {
+                    write!(f, "{empty}")?;
+                    empty = 0;
+                }
+                write!(f, "{piece}")?;
+            }
+            if empty != 0 {
+                write!(f, "{empty}")?;
+            }
+        }
+        let name = |card: Card| card.name().replace(' ', "-");
+        for [a, b] in self.hands {
+            write!(f, " {},{}", name(a), name(b))?;
+        }
+        let to_move = if self.to_move == 0 { 'o' } else { 'x' };
+        write!(f, " {} {to_move}", name(self.side))
+    }
+}
+
+impl Position {
+    // the piece on square `i` as it is written in the notation
+    // [Position::play] leaves a taken king on its square, so the king is only there if the piece is
+    fn square(&self, i: u32) -> Option<char> {
+        for (player, [king, pawn]) in [['O', 'o'], ['X', 'x']].into_iter().enumerate() {
+            if self.pieces[player] & 1 << i != 0 {
+                return Some(if self.kings[player] == i { king } else { pawn });
+            }
+        }
+        None
+    }
+
+    // the board with player 1 at the top, the cards of both players and the player to move
+    // or the winner if the game is over
+    // the squares of every row are written next to it, they are used by [Move]
+    pub fn pretty(&self) -> String {
+        let [hand0, hand1] = self.hands;
+        debug_assert_eq!(self.pieces[0] & self.pieces[1], 0);
+
+        let mut out = format!("x has {} and {}\n", hand1[0], hand1[1]);
+        out.push_str("----- x side\n");
+        for y in 0..5 {
+            let mut squares = String::new();
+            for x in 0..5 {
+                let i = 24 - 5 * y - x;
+                out.push(self.square(i).unwrap_or('.'));
+                squares.push_str(&format!(" {i:2}"));
+            }
+            out.push_str(&format!("  {squares}\n"));
+        }
+        out.push_str("----- o side\n");
+        out.push_str(&format!(
+            "o has {} and {}, {} is on the side\n",
+            hand0[0], hand0[1], self.side
+        ));
+        match self.winner() {
+            Some(winner) => {
+                let winner = if winner == 0 { 'o' } else { 'x' };
+                out.push_str(&format!("{winner} won\n"));
+            }
+            None => {
+                let to_move = if self.to_move == 0 { 'o' } else { 'x' };
+                out.push_str(&format!("{to_move} to move\n"));
+            }
+        }
+        out
+    }
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [board, hand0, hand1, side, to_move] = fields[..] else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+
+        let mut pieces = [0u32; 2];
+        let mut kings = [None; 2];
+        let rows: Vec<&str> = board.split('/').collect();
+        if rows.len() != 5 {
+            return Err(format!("expected 5 rows, got {}", rows.len()));
+        }
+        for (y, row) in rows.into_iter().enumerate() {
+            let mut x = 0;
+            for c in row.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    x += empty;
+                    continue;
+                }
+                if x >= 5 {
+                    return Err(format!("row {row} is longer than 5 squares"));
+                }
+                let i = 24 - 5 * y as u32 - x;
+                let (player, king) = match c {
+                    'O' => (0, true),
+                    'o' => (0, false),
+                    'X' => (1, true),
+                    'x' => (1, false),
+                    _ => return Err(format!("unknown piece {c}")),
+                };
+                pieces[player] |= 1 << i;
+                if king {
+                    if kings[player].is_some() {
+                        return Err(format!("player {player} has two kings"));
+                    }
+                    kings[player] = Some(i);
+                }
+                x += 1;
+            }
+            if x != 5 {
+                return Err(format!("row {row} does not have 5 squares"));
+            }
+        }
+        let king = |player: usize| kings[player].ok_or(format!("player {player} has no king"));
+        let kings = [king(0)?, king(1)?];
+
+        let hand = |hand: &str| -> Result<[Card; 2], String> {
+            match hand.split(',').collect::<Vec<_>>()[..] {
+                [a, b] => Ok([a.parse()?, b.parse()?]),
+                _ => Err(format!("expected 2 cards in hand {hand}")),
+            }
+        };
+        let hands = [hand(hand0)?, hand(hand1)?];
+        let side: Card = side.parse()?;
+        let mut cards = vec![side];
+        cards.extend(hands.iter().flatten());
+        cards.sort();
+        cards.dedup();
+        if cards.len() != 5 {
+            return Err("the same card is used twice".to_string());
+        }
+
+        let to_move = match to_move {
+            "o" => 0,
+            "x" => 1,
+            _ => return Err(format!("expected o or x to move, got {to_move}")),
+        };
+        Ok(Position {
+            pieces,
+            kings,
+            hands,
+            side,
+            to_move,
+        })
+    }
+}
+
+// a move is written as the card and the squares, like `Ox:7-12` or `Sea-Snake:3-9`
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let card = self.card.name().replace(' ', "-");
+        write!(f, "{card}:{}-{}", self.from, self.to)
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("expected a move like Ox:7-12, got {s}");
+        let (card, squares) = s.split_once(':').ok_or_else(err)?;
+        let (from, to) = squares.split_once('-').ok_or_else(err)?;
+        let square = |square: &str| match square.parse() {
+            Ok(square) if square < 25 => Ok(square),
+            _ => Err(format!("expected a square from 0 to 24, got {square}")),
+        };
+        Ok(Move {
+            card: card.parse()?,
+            from: square(from)?,
+            to: square(to)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onitama_simd::{AllTables, Card, PawnCount};
+
+    use super::{Move, Position};
+
+    #[test]
+    fn move_notation() {
+        let pos: Position = "1xXx1/5/5/5/1oO2 Ox,Boar Horse,Sea-Snake Crab x"
+            .parse()
+            .unwrap();
+        for mv in pos.moves() {
+            assert_eq!(mv.to_string().parse::<Move>(), Ok(mv));
+        }
+        let mv = Move {
+            card: Card::new(30).unwrap(),
+            from: 22,
+            to: 17,
+        };
+        assert_eq!(mv.to_string(), "Sea-Snake:22-17");
+        for bad in ["Ox 7-12", "Ox:7", "Ox:7-25", "Pig:7-12"] {
+            assert!(bad.parse::<Move>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn notation() {
+        let card = |index| Card::new(index).unwrap();
+        let pos = Position {
+            pieces: [1 << 2 | 1 << 3, 1 << 21 | 1 << 22 | 1 << 23],
+            kings: [2, 22],
+            hands: [[card(0), card(1)], [card(2), card(30)]],
+            side: card(4),
+            to_move: 1,
+        };
+        let s = "1xXx1/5/5/5/1oO2 Ox,Boar Horse,Sea-Snake Crab x";
+        assert_eq!(pos.to_string(), s);
+        assert_eq!(s.parse::<Position>(), Ok(pos));
+        let pretty = pos.pretty();
+        assert!(pretty.contains(".xXx.   24 23 22 21 20\n"));
+        assert!(pretty.contains(".oO..    4  3  2  1  0\n"));
+        assert!(pretty.contains("o has Ox and Boar, Crab is on the side"));
+        // names are not case sensitive and can be written without a space
+        let lower = "1xXx1/5/5/5/1oO2 ox,boar horse,seasnake crab x";
+        assert_eq!(lower.parse::<Position>(), Ok(pos));
+
+        for bad in [
+            "1xXx1/5/5/5 Ox,Boar Horse,Crab Elephant x",
+            "1xXx1/5/5/5/1oO3 Ox,Boar Horse,Crab Elephant x",
+            "1xXx1/5/5/5/1oo2 Ox,Boar Horse,Crab Elephant x",
+            "1xXX1/5/5/5/1oO2 Ox,Boar Horse,Crab Elephant x",
+            "1xXx1/5/5/5/1oO2 Ox,Boar Horse,Ox Elephant x",
+            "1xXx1/5/5/5/1oO2 Ox Horse,Crab Elephant x",
+            "1xXx1/5/5/5/1oO2 Ox,Boar Horse,Crab Elephant y",
+            "1xXx1/5/5/5/1oO2 Ox,Boar Horse,Crab",
+        ] {
+            assert!(bad.parse::<Position>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn king_taken() {
+        let pos: Position = "5/2X2/2O2/5/5 Boar,Horse Ox,Elephant Crab x"
+            .parse()
+            .unwrap();
+        let mv = "Ox:17-12".parse::<Move>().unwrap();
+        assert!(pos.moves().contains(&mv));
+        let next = pos.play(mv);
+        assert_eq!(next.winner(), Some(1));
+        assert_eq!(
+            next.to_string(),
+            "5/5/2X2/5/5 Boar,Horse Crab,Elephant Ox o"
+        );
+        let pretty = next.pretty();
+        assert!(pretty.contains("..X..   14 13 12 11 10\n"));
+        assert!(pretty.contains(".....   19 18 17 16 15\n"));
+        assert!(pretty.contains("x won\n"));
+    }
+
+    // every state of a table is written and read back to the same layout, kings and bit
+    #[test]
+    fn notation_round_trip() {
+        let tb = AllTables::build(2, 0b11111);
+        for counts in [
+            PawnCount {
+                count0: 1,
+                count1: 0,
+            },
+            PawnCount {
+                count0: 0,
+                count1: 1,
+            },
+        ] {
+            tb.for_each_state(counts, |pos, layout, kpos, bit| {
+                let parsed: Position = pos.to_string().parse().unwrap();
+                assert_eq!(parsed, pos);
+                let (c, l, k, b) = tb.locate(&parsed).unwrap();
+                assert_eq!(c, counts);
+                assert_eq!((l.pieces0, l.pieces1), (layout.pieces0, layout.pieces1));
+                assert_eq!((k.king0, k.king1), (kpos.king0, kpos.king1));
+                assert_eq!(b, bit);
+            });
+        }
+    }
+}
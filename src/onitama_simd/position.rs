@@ -0,0 +1,300 @@
+use std::sync::atomic::Ordering;
+
+use crate::index::Indexer;
+
+use super::{count_indexer, AllTables, Block, Card, KingPos, PawnCount, TeamLayout};
+
+// a concrete game state
+// squares are numbered like the tables, 0..5 is the back row of player 0
+// player 0 defends the temple on square 2 and player 1 the temple on square 22
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    // all pieces of each player, including the king
+    pub pieces: [u32; 2],
+    pub kings: [u32; 2],
+    pub hands: [[Card; 2]; 2],
+    pub side: Card,
+    // 0 or 1
+    pub to_move: usize,
+}
+
+// result for the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Position {
+    // the same position with the board turned around and the players swapped
+    // both positions have the same outcome for the player to move
+    pub fn invert(&self) -> Position {
+        let rotate = |pieces: u32| pieces.reverse_bits() >> 7;
+        Position {
+            pieces: [rotate(self.pieces[1]), rotate(self.pieces[0])],
+            kings: [24 - self.kings[1], 24 - self.kings[0]],
+            hands: [self.hands[1], self.hands[0]],
+            side: self.side,
+            to_move: 1 - self.to_move,
+        }
+    }
+}
+
+impl AllTables {
+    // returns whether the player to move wins
+    // returns `None` if the position is not part of the tables
+    pub fn probe(&self, pos: &Position) -> Option<bool> {
+        let (counts, layout, kpos, bit) = self.locate(pos)?;
+        let table = self.index_count(counts);
+        let val = table.index(layout)[kpos].load(Ordering::Relaxed);
+        Some(val & 1 << bit != 0)
+    }
+
+    // if the tables were built without losses, they are found by probing every move
+    // returns `None` if the position is not part of the tables
+    pub fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+        if !self.wdl {
+            return self.probe_wdl_slow(pos);
+        }
+        let (counts, layout, kpos, bit) = self.locate(pos)?;
+        let table = self.index_count(counts);
+        let win = table.index(layout)[kpos].load(Ordering::Relaxed);
+        let loss = table.index_losses(layout)[kpos].load(Ordering::Relaxed);
+        Some(if win & 1 << bit != 0 {
+            Wdl::Win
+        } else if loss & 1 << bit != 0 {
+            Wdl::Loss
+        } else {
+            Wdl::Draw
+        })
+    }
+
+    // a position is lost when every move leads to a win for the opponent
+    fn probe_wdl_slow(&self, pos: &Position) -> Option<Wdl> {
+        if self.probe(pos)? {
+            return Some(Wdl::Win);
+        }
+        for mv in pos.moves() {
+            let next = pos.play(mv);
+            if next.winner().is_none() && !self.probe(&next)? {
+                return Some(Wdl::Draw);
+            }
+        }
+        Some(Wdl::Loss)
+    }
+
+    // distance to conversion, the plies until the game ends or a piece is taken
+    // see [BuildConfig::dtc]
+    // it is odd for wins, even for losses and 0 for draws
+    // returns `None` if the position is not part of the tables
+    // or if the tables were built without distances
+    pub fn probe_dtc(&self, pos: &Position) -> Option<u8> {
+        if !self.dtc {
+            return None;
+        }
+        let (counts, layout, kpos, bit) = self.locate(pos)?;
+        let table = self.index_count(counts);
+        let block = table.block_index(layout, kpos);
+        Some(table.dtc(block, bit).load(Ordering::Relaxed))
+    }
+
+    // the tables store positions where player 1 is to move
+    // so positions with player 0 to move are inverted first
+    pub(crate) fn locate(&self, pos: &Position) -> Option<(PawnCount, TeamLayout, KingPos, u32)> {
+        let [pieces0, pieces1] = pos.pieces;
+        let [king0, king1] = pos.kings;
+        if pos.to_move > 1
+            || (pieces0 | pieces1) >> 25 != 0
+            || pieces0 & pieces1 != 0
+            || king0 >= 25
+            || king1 >= 25
+            || pieces0 & 1 << king0 == 0
+            || pieces1 & 1 << king1 == 0
+            || pieces0.count_ones() > self.size
+            || pieces1.count_ones() > self.size
+        {
+            return None;
+        }
+
+        let mut layout = TeamLayout { pieces0, pieces1 };
+        let mut kpos = KingPos { king0, king1 };
+        let mut hands = pos.hands;
+        let mut side = pos.side;
+        if pos.to_move == 0 {
+            layout = layout.invert();
+            kpos = kpos.invert();
+            hands.swap(0, 1);
+        }
+        // only one of the mirrored layouts is stored
+        if self.is_mirrored() && !layout.is_canonical(layout.counts()) {
+            layout = layout.mirror();
+            kpos = kpos.mirror();
+            hands = hands.map(|hand| hand.map(Card::mirror));
+            side = side.mirror();
+        }
+        // the game is already over
+        if kpos.king0 == 22 || kpos.king1 == 2 {
+            return None;
+        }
+
+        let hand = |[a, b]: [Card; 2]| Some([self.cards.position(a)?, self.cards.position(b)?]);
+        let (hand0, hand1) = (hand(hands[0])?, hand(hands[1])?);
+        let side = self.cards.position(side)?;
+        let mut used = [hand0, hand1].concat();
+        used.push(side);
+        used.sort();
+        used.dedup();
+        if used.len() != 5 {
+            return None;
+        }
+
+        let bit = Block::bit(hand0, hand1, side)?;
+        Some((layout.counts(), layout, kpos, bit))
+    }
+
+    // the position stored at bit `bit` of block `index` of the table with these pawn counts
+    // this is the inverse of finding a position in the tables, player 1 is to move
+    // every layout has room for the largest number of king positions, so some blocks are not used
+    // returns `None` for those blocks and if the table, the index or the bit is out of range
+    pub fn state_at(&self, counts: PawnCount, index: usize, bit: u32) -> Option<Position> {
+        let tables = count_indexer(self.size);
+        if counts.count0 >= self.size || counts.count1 >= self.size || bit >= 30 {
+            return None;
+        }
+        let table = &self.list[tables.index(&counts)];
+        if index >= table.list.len() {
+            return None;
+        }
+        let (i, king) = (index / table.chunk_size, index % table.chunk_size);
+        let i = match &table.canonical {
+            None => i,
+            Some(canonical) => canonical.select(i),
+        };
+        let layout = counts.unindex(i);
+        let kings = layout.indexer(counts);
+        if king >= kings.total() {
+            return None;
+        }
+        Some(self.position(layout, kings.unindex(king), bit))
+    }
+
+    // the position stored at a bit of a block, player 1 is to move
+    pub(crate) fn position(&self, layout: TeamLayout, kpos: KingPos, bit: u32) -> Position {
+        let cards: Vec<Card> = self.cards.iter().collect();
+        let ([a, b], [c, d], side) = Block::split(bit);
+        Position {
+            pieces: [layout.pieces0, layout.pieces1],
+            kings: [kpos.king0, kpos.king1],
+            hands: [[cards[a], cards[b]], [cards[c], cards[d]]],
+            side: cards[side],
+            to_move: 1,
+        }
+    }
+
+    // calls `f` with every state of a table, like [AllTables::position]
+    #[cfg(test)]
+    pub(crate) fn for_each_state(
+        &self,
+        counts: PawnCount,
+        mut f: impl FnMut(Position, TeamLayout, KingPos, u32),
+    ) {
+        use crate::index::InternalIter;
+
+        for layout in counts {
+            layout.indexer(counts).for_each(|kpos| {
+                for bit in 0..30 {
+                    f(self.position(layout, *kpos, bit), layout, *kpos, bit);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::Indexer,
+        onitama_simd::{AllTables, BuildConfig, Card, PawnCount},
+    };
+
+    use super::Position;
+
+    // ox, boar, horse, elephant, crab
+    fn card(index: usize) -> Card {
+        Card::new(index).unwrap()
+    }
+
+    #[test]
+    fn locate_round_trip() {
+        let tb = AllTables::build(2, 0b11111);
+        let counts = PawnCount {
+            count0: 1,
+            count1: 0,
+        };
+        let table = tb.index_count(counts);
+        tb.for_each_state(counts, |pos, layout, kpos, bit| {
+            let (c, l, k, b) = tb.locate(&pos).unwrap();
+            assert_eq!(c, counts);
+            assert_eq!((l.pieces0, l.pieces1), (layout.pieces0, layout.pieces1));
+            assert_eq!((k.king0, k.king1), (kpos.king0, kpos.king1));
+            assert_eq!(b, bit);
+
+            let val = table.index(layout)[kpos].load(std::sync::atomic::Ordering::Relaxed);
+            assert_eq!(tb.probe(&pos), Some(val & 1 << bit != 0));
+        });
+    }
+
+    #[test]
+    fn state_at() {
+        let tb = AllTables::build(2, 0b11111);
+        let mirrored = BuildConfig::new(2, 0b11111).mirror(true).build();
+        for tb in [&tb, &mirrored] {
+            for counts in tb.list.iter().map(|table| table.counts) {
+                let table = tb.index_count(counts);
+                let mut found = 0;
+                for index in 0..table.list.len() {
+                    let Some(pos) = tb.state_at(counts, index, 7) else {
+                        continue;
+                    };
+                    found += 1;
+                    let (c, layout, kpos, bit) = tb.locate(&pos).unwrap();
+                    assert_eq!((c, bit), (counts, 7));
+                    assert_eq!(table.block_index(layout, kpos), index);
+                }
+                let total: usize = counts.into_iter().map(|l| l.indexer(counts).total()).sum();
+                assert!(found == total || tb.is_mirrored() && found < total);
+            }
+        }
+        let counts = PawnCount {
+            count0: 2,
+            count1: 0,
+        };
+        assert_eq!(tb.state_at(counts, 0, 0), None);
+    }
+
+    #[test]
+    fn probe_king_capture() {
+        let tb = AllTables::build(1, 0b11111);
+        // the kings face each other and the player to move has the ox
+        let mut pos = Position {
+            pieces: [1 << 12, 1 << 17],
+            kings: [12, 17],
+            hands: [[card(1), card(2)], [card(0), card(3)]],
+            side: card(4),
+            to_move: 1,
+        };
+        assert_eq!(tb.probe(&pos), Some(true));
+
+        pos.hands.swap(0, 1);
+        pos.to_move = 0;
+        assert_eq!(tb.probe(&pos), Some(true));
+
+        // the same card can not be used twice
+        pos.side = card(0);
+        assert_eq!(tb.probe(&pos), None);
+        // a card that is not part of the tables
+        pos.side = card(5);
+        assert_eq!(tb.probe(&pos), None);
+    }
+}
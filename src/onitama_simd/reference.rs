@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use super::{Card, Cards, Position, Wdl};
+
+// a slow solver that does not share any code with the build, to check the tables against
+// it stores every state in a HashMap and finds the results by repeated minimax over all states
+// like the tables, a player without moves loses
+pub struct Reference {
+    states: Vec<Position>,
+    index: HashMap<Position, usize>,
+    results: Vec<Wdl>,
+}
+
+impl Reference {
+    // all states with at most `size` pieces per player and `max_pieces` pieces in total
+    // taking a piece leads to a state with fewer pieces, so these states are closed under moves
+    pub fn solve(cards: u64, size: u32, max_pieces: u32) -> Self {
+        let cards: Vec<Card> = Cards(cards).iter().collect();
+        assert_eq!(cards.len(), 5, "expected a set of 5 cards");
+
+        let sets = piece_sets(size);
+        let mut states = vec![];
+        for &pieces0 in &sets {
+            for &pieces1 in &sets {
+                let total = pieces0.count_ones() + pieces1.count_ones();
+                if pieces0 & pieces1 != 0 || total > max_pieces {
+                    continue;
+                }
+                for king0 in bits(pieces0) {
+                    for king1 in bits(pieces1) {
+                        for (hands, side) in deals(&cards) {
+                            for to_move in 0..2 {
+                                let pos = Position {
+                                    pieces: [pieces0, pieces1],
+                                    kings: [king0, king1],
+                                    hands,
+                                    side,
+                                    to_move,
+                                };
+                                if pos.winner().is_none() {
+                                    states.push(pos);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let index: HashMap<Position, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| (*pos, i))
+            .collect();
+
+        // `None` means that the move ends the game
+        let moves: Vec<Vec<Option<usize>>> = states
+            .iter()
+            .map(|pos| {
+                pos.moves()
+                    .into_iter()
+                    .map(|mv| {
+                        let next = normalize(pos.play(mv));
+                        next.winner().is_none().then(|| index[&next])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut results: Vec<Option<Wdl>> = vec![None; states.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..states.len() {
+                if results[i].is_some() {
+                    continue;
+                }
+                let result = |next: &Option<usize>| next.map_or(Some(Wdl::Loss), |j| results[j]);
+                if moves[i].iter().any(|next| result(next) == Some(Wdl::Loss)) {
+                    results[i] = Some(Wdl::Win);
+                    changed = true;
+                } else if moves[i].iter().all(|next| result(next) == Some(Wdl::Win)) {
+                    results[i] = Some(Wdl::Loss);
+                    changed = true;
+                }
+            }
+        }
+
+        Self {
+            states,
+            index,
+            results: results
+                .into_iter()
+                .map(|r| r.unwrap_or(Wdl::Draw))
+                .collect(),
+        }
+    }
+
+    // returns `None` if the position is not one of the states
+    pub fn get(&self, pos: &Position) -> Option<Wdl> {
+        let i = *self.index.get(&normalize(*pos))?;
+        Some(self.results[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Position, Wdl)> {
+        self.states.iter().zip(self.results.iter().copied())
+    }
+}
+
+// the order of the cards in a hand does not matter
+fn normalize(mut pos: Position) -> Position {
+    for hand in &mut pos.hands {
+        hand.sort();
+    }
+    pos
+}
+
+fn bits(mask: u32) -> impl Iterator<Item = u32> {
+    (0..25).filter(move |i| mask & 1 << i != 0)
+}
+
+// every set of 1 to `size` squares
+fn piece_sets(size: u32) -> Vec<u32> {
+    let mut sets = vec![];
+    let mut last = vec![0u32];
+    for _ in 0..size {
+        // only add squares above the highest square, so every set is made once
+        last = last
+            .iter()
+            .flat_map(|&set| (32 - set.leading_zeros()..25).map(move |i| set | 1 << i))
+            .collect();
+        sets.extend(&last);
+    }
+    sets
+}
+
+// every way to give two cards to each player and put the last one on the side
+fn deals(cards: &[Card]) -> Vec<([[Card; 2]; 2], Card)> {
+    let mut deals = vec![];
+    for a in 0..5 {
+        for b in a + 1..5 {
+            for c in 0..5 {
+                for d in c + 1..5 {
+                    if [a, b].contains(&c) || [a, b].contains(&d) {
+                        continue;
+                    }
+                    let side = (0..5).find(|s| ![a, b, c, d].contains(s)).unwrap();
+                    deals.push(([[cards[a], cards[b]], [cards[c], cards[d]]], cards[side]));
+                }
+            }
+        }
+    }
+    deals
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onitama_simd::{base_card_sets, BuildConfig};
+
+    use super::Reference;
+
+    fn compare(cards: u64, size: u32, max_pieces: u32) {
+        let tb = BuildConfig::new(size, cards).wdl(true).build();
+        let reference = Reference::solve(cards, size, max_pieces);
+        for (pos, wdl) in reference.iter() {
+            assert_eq!(
+                tb.probe_wdl(pos),
+                Some(wdl),
+                "{pos:?} with cards {cards:#b}"
+            );
+        }
+    }
+
+    #[test]
+    fn deals() {
+        let cards: Vec<_> = super::Cards(0b11111).iter().collect();
+        assert_eq!(super::deals(&cards).len(), 30);
+    }
+
+    #[test]
+    fn reference_kings() {
+        for cards in base_card_sets().step_by(97) {
+            compare(cards, 1, 2);
+        }
+    }
+
+    #[test]
+    fn reference_pawn() {
+        // ox, boar, horse, elephant and crab
+        compare(0b11111, 2, 3);
+        // tiger, dragon, frog, rabbit and cobra
+        compare(1 << 5 | 1 << 8 | 1 << 10 | 1 << 11 | 1 << 15, 2, 3);
+    }
+
+    // takes a long time, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn reference_all_card_sets() {
+        for cards in base_card_sets() {
+            compare(cards, 1, 2);
+            compare(cards, 2, 3);
+        }
+    }
+}
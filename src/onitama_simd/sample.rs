@@ -0,0 +1,175 @@
+use std::{fmt, io, str::FromStr};
+
+use super::{AllTables, PawnCount, Position};
+
+// a position of the tables together with whether the player to move wins
+// it is written as one line, the notation of the position followed by 1 for a win and 0 otherwise
+// like `1xXx1/5/5/5/2O2 Ox,Boar Horse,Crab Elephant x 1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub position: Position,
+    pub win: bool,
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.position, self.win as u8)
+    }
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (position, win) = s
+            .trim()
+            .rsplit_once(' ')
+            .ok_or("expected a position and a label")?;
+        let win = match win {
+            "1" => true,
+            "0" => false,
+            _ => return Err(format!("expected 1 or 0 as label, got {win}")),
+        };
+        Ok(Sample {
+            position: position.parse()?,
+            win,
+        })
+    }
+}
+
+// draws positions from the tables uniformly at random, see [AllTables::sampler]
+// the same seed always gives the same samples
+pub struct Sampler<'a> {
+    tb: &'a AllTables,
+    rng: SplitMix,
+    // every table with the number of blocks in the tables before it
+    tables: Vec<(PawnCount, usize)>,
+    blocks: usize,
+}
+
+impl AllTables {
+    // every position that is part of the tables is equally likely, for both players to move
+    // a block is chosen with the tables weighted by their size, then a bit of that block
+    // blocks that are not used by any layout are skipped, see [AllTables::state_at]
+    pub fn sampler(&self, seed: u64) -> Sampler<'_> {
+        let mut tables = vec![];
+        let mut blocks = 0;
+        for table in self.list.iter() {
+            tables.push((table.counts, blocks));
+            blocks += table.list.len();
+        }
+        Sampler {
+            tb: self,
+            rng: SplitMix(seed),
+            tables,
+            blocks,
+        }
+    }
+}
+
+impl Sampler<'_> {
+    // one sample per line, see [Sample]
+    pub fn write_text(&mut self, n: usize, mut w: impl io::Write) -> io::Result<()> {
+        for sample in self.take(n) {
+            writeln!(w, "{sample}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Sampler<'_> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.blocks == 0 {
+            return None;
+        }
+        loop {
+            let block = self.rng.below(self.blocks);
+            let i = self.tables.partition_point(|&(_, start)| start <= block) - 1;
+            let (counts, start) = self.tables[i];
+            let bit = self.rng.below(30) as u32;
+            let Some(mut position) = self.tb.state_at(counts, block - start, bit) else {
+                continue;
+            };
+
+            // a canonical layout also stands for its mirror
+            // unless it is its own mirror, then the mirrored states are in the same layout
+            // so half of the samples of those layouts are skipped
+            if self.tb.is_mirrored() && self.rng.below(2) == 1 {
+                let mirror = position.mirror();
+                if (mirror.pieces[0], mirror.pieces[1]) == (position.pieces[0], position.pieces[1])
+                {
+                    continue;
+                }
+                position = mirror;
+            }
+            // the tables only store positions with player 1 to move
+            if self.rng.below(2) == 1 {
+                position = position.invert();
+            }
+
+            let win = self
+                .tb
+                .probe(&position)
+                .expect("the sample is part of the tables");
+            return Some(Sample { position, win });
+        }
+    }
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // a number below `n`, the bias is too small to matter for the tables
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onitama_simd::{AllTables, BuildConfig};
+
+    use super::Sample;
+
+    #[test]
+    fn sampler() {
+        let tb = AllTables::build(2, 0b11111);
+        let samples: Vec<Sample> = tb.sampler(1).take(20000).collect();
+        assert_eq!(samples, tb.sampler(1).take(20000).collect::<Vec<_>>());
+        assert_ne!(samples[..10], tb.sampler(2).take(10).collect::<Vec<_>>());
+
+        let mut text = vec![];
+        tb.sampler(1).write_text(100, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        for (line, sample) in text.lines().zip(&samples) {
+            assert_eq!(line.parse::<Sample>().as_ref(), Ok(sample));
+        }
+
+        // the part of the samples that is won is close to the part of the states that is won
+        let rate = |samples: &[Sample]| {
+            samples.iter().filter(|s| s.win).count() as f64 / samples.len() as f64
+        };
+        let expected = tb.count_ones() as f64 / (tb.len() * 30) as f64;
+        assert!((rate(&samples) - expected).abs() < 0.02);
+        let to_move = samples.iter().filter(|s| s.position.to_move == 0).count();
+        assert!((to_move as f64 / samples.len() as f64 - 0.5).abs() < 0.02);
+
+        let mirrored = BuildConfig::new(2, 0b11111).mirror(true).build();
+        let samples: Vec<Sample> = mirrored.sampler(1).take(20000).collect();
+        assert!((rate(&samples) - expected).abs() < 0.02);
+        for sample in &samples {
+            assert_eq!(tb.probe(&sample.position), Some(sample.win));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::index::Indexer;
+
+use super::{count_indexer, PawnCount};
+
+// every entry of the schedule solves a pawn count together with its inverse
+// an entry reads the finished tables with one pawn less for the player to move
+// and it writes the wins that lead to taking a piece to the tables with one more pawn
+impl PawnCount {
+    // the pawn counts that are solved by the entry of this pawn count
+    pub(crate) fn with_inverse(self) -> impl Iterator<Item = PawnCount> {
+        let inverse = (self.count0 != self.count1).then(|| self.invert());
+        [self].into_iter().chain(inverse)
+    }
+
+    // the table of the states after taking a pawn of player 1
+    pub(crate) fn take_one(self) -> Option<PawnCount> {
+        (self.count1 != 0).then(|| PawnCount {
+            count0: self.count0,
+            count1: self.count1 - 1,
+        })
+    }
+
+    // the table of the states before player 0 lost a pawn
+    pub(crate) fn leave_one(self, size: u32) -> Option<PawnCount> {
+        (self.count0 + 1 != size).then(|| PawnCount {
+            count0: self.count0 + 1,
+            count1: self.count1,
+        })
+    }
+
+    // the pawn count of the entry that solves this pawn count
+    fn entry(self) -> PawnCount {
+        if self.count0 >= self.count1 {
+            self
+        } else {
+            self.invert()
+        }
+    }
+}
+
+// the entries in the order they are solved, together with their level
+// an entry only depends on entries of lower levels, so the entries of one level can be solved at once
+pub(crate) fn schedule(size: u32) -> Vec<(PawnCount, usize)> {
+    let indexer = count_indexer(size);
+    let entries: Vec<PawnCount> = count_indexer(size)
+        .into_iter()
+        .filter(|counts: &PawnCount| counts.count0 >= counts.count1)
+        .collect();
+
+    let mut levels: Vec<Option<usize>> = vec![None; indexer.total()];
+    // the highest level of the entries that write to the tables of an entry
+    let mut writers: Vec<Option<usize>> = vec![None; indexer.total()];
+    for &counts in &entries {
+        let level = |counts: PawnCount| {
+            let level = levels[indexer.index(&counts.entry())];
+            level.expect("the dependencies of an entry come before it")
+        };
+        let read = counts.with_inverse().filter_map(PawnCount::take_one);
+        let level = read
+            .map(level)
+            .chain(writers[indexer.index(&counts)])
+            .map(|level| level + 1)
+            .max()
+            .unwrap_or(0);
+        levels[indexer.index(&counts)] = Some(level);
+
+        for written in counts.with_inverse().filter_map(|c| c.leave_one(size)) {
+            let writer = &mut writers[indexer.index(&written.entry())];
+            *writer = Some(writer.map_or(level, |w| w.max(level)));
+        }
+    }
+
+    let mut schedule: Vec<(PawnCount, usize)> = entries
+        .into_iter()
+        .map(|counts| (counts, levels[indexer.index(&counts)].unwrap()))
+        .collect();
+    // the sort is stable, so the entries of one level keep their order
+    schedule.sort_by_key(|&(_, level)| level);
+    schedule
+}
+
+// indices of all tables that are used by the entry
+pub(crate) fn entry_tables(size: u32, counts: PawnCount) -> impl Iterator<Item = usize> {
+    let take_one = counts.with_inverse().filter_map(PawnCount::take_one);
+    let indexer = count_indexer(size);
+    entry_written(size, counts).chain(take_one.map(move |counts| indexer.index(&counts)))
+}
+
+// indices of the tables that are changed by the entry
+pub(crate) fn entry_written(size: u32, counts: PawnCount) -> impl Iterator<Item = usize> {
+    let leave_one = counts
+        .with_inverse()
+        .filter_map(move |counts| counts.leave_one(size));
+    let indexer = count_indexer(size);
+    [counts, counts.invert()]
+        .into_iter()
+        .chain(leave_one)
+        .map(move |counts| indexer.index(&counts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schedule;
+
+    #[test]
+    fn schedule_levels() {
+        for size in 1..=5 {
+            let schedule = schedule(size);
+            assert_eq!(schedule.len(), (size * (size + 1) / 2) as usize);
+            for (i, &(counts, level)) in schedule.iter().enumerate() {
+                let before = |c| schedule[..i].iter().any(|&(e, l)| e == c && l < level);
+                for c in counts.with_inverse() {
+                    if let Some(take_one) = c.take_one() {
+                        assert!(before(take_one.entry()));
+                    }
+                    // nothing that writes to this entry comes after it
+                    for &(other, _) in &schedule[i..] {
+                        for o in other.with_inverse() {
+                            assert_ne!(o.leave_one(size), Some(c));
+                        }
+                    }
+                }
+            }
+        }
+        // (2, 0) and (1, 1) both only depend on (1, 0)
+        let levels: Vec<usize> = schedule(3).iter().map(|&(_, level)| level).collect();
+        assert!(levels.windows(2).any(|w| w[0] == w[1]));
+    }
+}
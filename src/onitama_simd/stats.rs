@@ -0,0 +1,179 @@
+use std::{io, time::Duration};
+
+use super::{Cards, PawnCount};
+
+// results of solving one pawn count, see [BuildStats]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableStats {
+    pub counts: PawnCount,
+    // states that are won for the player to move once the table is finished
+    pub wins: u64,
+    // states that are won before the first iteration, by winning at once or taking a piece
+    pub win_in1: u64,
+    // states that are neither won nor lost, these are draws
+    pub unresolved: u64,
+    pub iterations: u16,
+    // a pawn count and its inverse are solved together, so they have the same time
+    pub elapsed: Duration,
+    // number of layouts that were resolved before and after going up, see [BuildEvent::GoUp]
+    pub resolved: [u64; 2],
+    // blocks that were not updated because all their states were already won or lost
+    // see [BuildConfig::skip_resolved]
+    pub skipped: u64,
+}
+
+// the results of [BuildConfig::build] for every pawn count, in the order they were solved
+// tables that are loaded from a file have no stats
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildStats {
+    pub size: u32,
+    pub cards: u64,
+    pub tables: Vec<TableStats>,
+    // only the time of the last run if the build was continued from a checkpoint
+    pub elapsed: Duration,
+}
+
+impl BuildStats {
+    pub(crate) fn new(size: u32, cards: u64) -> Self {
+        Self {
+            size,
+            cards,
+            ..Default::default()
+        }
+    }
+
+    pub fn table(&self, counts: PawnCount) -> Option<&TableStats> {
+        self.tables.iter().find(|stats| stats.counts == counts)
+    }
+
+    pub fn wins(&self) -> u64 {
+        self.tables.iter().map(|stats| stats.wins).sum()
+    }
+
+    pub fn win_in1(&self) -> u64 {
+        self.tables.iter().map(|stats| stats.win_in1).sum()
+    }
+
+    pub fn unresolved(&self) -> u64 {
+        self.tables.iter().map(|stats| stats.unresolved).sum()
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.tables
+            .iter()
+            .map(|stats| stats.iterations as u64)
+            .sum()
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.tables.iter().map(|stats| stats.skipped).sum()
+    }
+
+    // one object with the settings and the totals, and a list with one object per table
+    pub fn write_json(&self, mut w: impl io::Write) -> io::Result<()> {
+        let names: Vec<String> = Cards(self.cards)
+            .iter()
+            .map(|card| format!("{:?}", card.name()))
+            .collect();
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"size\": {},", self.size)?;
+        writeln!(w, "  \"cards\": [{}],", names.join(", "))?;
+        writeln!(w, "  \"wins\": {},", self.wins())?;
+        writeln!(w, "  \"win_in1\": {},", self.win_in1())?;
+        writeln!(w, "  \"unresolved\": {},", self.unresolved())?;
+        writeln!(w, "  \"skipped\": {},", self.skipped())?;
+        writeln!(w, "  \"seconds\": {},", self.elapsed.as_secs_f64())?;
+        writeln!(w, "  \"tables\": [")?;
+        for (i, stats) in self.tables.iter().enumerate() {
+            let TableStats {
+                counts,
+                wins,
+                win_in1,
+                unresolved,
+                iterations,
+                elapsed,
+                resolved,
+                skipped,
+            } = stats;
+            write!(
+                w,
+                "    {{\"count0\": {}, \"count1\": {}, \"wins\": {wins}, \"win_in1\": {win_in1}, \
+                 \"unresolved\": {unresolved}, \"iterations\": {iterations}, \"seconds\": {}, \
+                 \"resolved\": [{}, {}], \"skipped\": {skipped}}}",
+                counts.count0,
+                counts.count1,
+                elapsed.as_secs_f64(),
+                resolved[0],
+                resolved[1],
+            )?;
+            writeln!(w, "{}", if i + 1 < self.tables.len() { "," } else { "" })?;
+        }
+        writeln!(w, "  ]")?;
+        writeln!(w, "}}")
+    }
+
+    // one row per table, the card set is the bit set of [AllTables::cards]
+    pub fn write_csv(&self, mut w: impl io::Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "size,cards,count0,count1,wins,win_in1,unresolved,iterations,seconds,resolved_before_go_up,resolved_after_go_up,skipped"
+        )?;
+        for stats in &self.tables {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                self.size,
+                self.cards,
+                stats.counts.count0,
+                stats.counts.count1,
+                stats.wins,
+                stats.win_in1,
+                stats.unresolved,
+                stats.iterations,
+                stats.elapsed.as_secs_f64(),
+                stats.resolved[0],
+                stats.resolved[1],
+                stats.skipped,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::Indexer,
+        onitama_simd::{AllTables, PawnCount},
+    };
+
+    #[test]
+    fn table_stats() {
+        let tb = AllTables::build(2, 0b11111);
+        let stats = tb.stats();
+        assert_eq!(stats.tables.len(), 4);
+        assert_eq!(stats.wins(), tb.count_ones());
+        for table in &stats.tables {
+            assert!(table.win_in1 <= table.wins);
+            let total = table.counts.total() as u64;
+            // every layout is updated again after going up
+            assert!(table.resolved[0] <= total && table.resolved[1] <= total);
+        }
+        let counts = PawnCount {
+            count0: 1,
+            count1: 1,
+        };
+        assert!(stats.table(counts).unwrap().iterations > 1);
+
+        let mut csv = vec![];
+        stats.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 5);
+
+        let mut json = vec![];
+        stats.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains(&format!("\"wins\": {},", tb.count_ones())));
+        assert!(json.contains("\"cards\": [\"Ox\", "));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io,
+    marker::PhantomData,
+    mem::size_of,
+    ops::{Deref, DerefMut},
+    path::Path,
+    slice,
+};
+
+use memmap2::MmapMut;
+#[cfg(unix)]
+use memmap2::{Advice, UncheckedAdvice};
+
+use super::{alloc_zeroed, AllTables};
+
+// the memory of one plane of a table, either on the heap or in a memory mapped file
+// only used for atomic integers, for which all zeroes is a valid value
+pub enum Storage<T> {
+    Heap(Box<[T]>),
+    Mapped {
+        map: MmapMut,
+        len: usize,
+        marker: PhantomData<T>,
+    },
+}
+
+impl<T> Storage<T> {
+    // in a file called `name` if a directory is given, otherwise on the heap
+    pub fn new(dir: Option<&Path>, name: &str, len: usize) -> io::Result<Self> {
+        match dir {
+            Some(dir) => Self::mapped(&dir.join(name), len),
+            None => Ok(Self::zeroed(len)),
+        }
+    }
+
+    pub fn zeroed(len: usize) -> Self {
+        Self::Heap(alloc_zeroed(len))
+    }
+
+    // the file is created with the right size and is zero filled
+    // an existing file at the same path is overwritten
+    pub fn mapped(path: &Path, len: usize) -> io::Result<Self> {
+        if len == 0 {
+            return Ok(Self::default());
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((len * size_of::<T>()) as u64)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self::Mapped {
+            map,
+            len,
+            marker: PhantomData,
+        })
+    }
+
+    // only keeps the first `len` elements, the file of a mapping keeps its size
+    pub fn truncate(&mut self, new_len: usize) {
+        match self {
+            Self::Heap(list) => {
+                let mut vec = std::mem::take(list).into_vec();
+                vec.truncate(new_len);
+                *list = vec.into_boxed_slice();
+            }
+            Self::Mapped { len, .. } => *len = new_len.min(*len),
+        }
+    }
+
+    // tell the os that the storage will be used soon
+    fn load(&self) {
+        #[cfg(unix)]
+        if let Self::Mapped { map, .. } = self {
+            map.advise(Advice::WillNeed).ok();
+        }
+    }
+
+    // write the storage to disk and allow the os to drop it from memory
+    fn release(&self) -> io::Result<()> {
+        if let Self::Mapped { map, .. } = self {
+            map.flush()?;
+            #[cfg(unix)]
+            unsafe {
+                map.unchecked_advise(UncheckedAdvice::DontNeed)?
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::Heap(Box::new([]))
+    }
+}
+
+impl<T> Deref for Storage<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Heap(list) => list,
+            Self::Mapped { map, len, .. } => unsafe {
+                slice::from_raw_parts(map.as_ptr() as *const T, *len)
+            },
+        }
+    }
+}
+
+impl<T> DerefMut for Storage<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            Self::Heap(list) => list,
+            Self::Mapped { map, len, .. } => unsafe {
+                slice::from_raw_parts_mut(map.as_mut_ptr() as *mut T, *len)
+            },
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Storage<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl AllTables {
+    // only the tables in `needed` are kept in memory, the others are written to disk
+    // this does nothing for tables on the heap
+    pub(crate) fn set_resident(&self, needed: &[usize]) -> io::Result<()> {
+        for (i, table) in self.list.iter().enumerate() {
+            if needed.contains(&i) {
+                table.list.load();
+                table.losses.load();
+                table.pending.load();
+                table.dtc.load();
+            } else {
+                table.list.release()?;
+                table.losses.release()?;
+                table.pending.release()?;
+                table.dtc.release()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::onitama_simd::BuildConfig;
+
+    #[test]
+    fn mapped_build() {
+        // the pid keeps test runs at the same time apart
+        let dir = std::env::temp_dir().join(format!("onitama_mapped_build_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let config = BuildConfig::new(2, 0b11111).dtc(true);
+        let tb = config.clone().dir(&dir).try_build().unwrap();
+        assert!(tb == config.build());
+        assert_eq!(tb.count_ones(), 6752579);
+        assert!(dir.join("1_1.wins").exists());
+        drop(tb);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
+};
+
+use crate::index::Indexer;
+
+use super::{count_indexer, AllTables, Card, Cards, PawnCount, Table};
+
+// file layout, all integers are little endian:
+// MAGIC, VERSION: u32, flags: u32, size: u32, cards: u64, num_tables: u32
+// then for every table in `count_indexer` order:
+// count0: u32, count1: u32, chunk_size: u64, len: u64, followed by `len` blocks as u32
+// and another `len` blocks with the losses if FLAG_LOSSES is set
+// and `len * 30` distances as u8 if FLAG_DTC is set
+// with FLAG_MIRROR only the canonical layouts are stored, see [BuildConfig::mirror]
+const MAGIC: &[u8; 8] = b"ONITAMA\0";
+const VERSION: u32 = 3;
+
+const FLAG_LOSSES: u32 = 1;
+const FLAG_DTC: u32 = 2;
+const FLAG_MIRROR: u32 = 4;
+
+// number of blocks that are converted at once
+const BUF_BLOCKS: usize = 1 << 12;
+
+impl AllTables {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        let mut flags = 0;
+        if self.wdl {
+            flags |= FLAG_LOSSES;
+        }
+        if self.dtc {
+            flags |= FLAG_DTC;
+        }
+        if self.is_mirrored() {
+            flags |= FLAG_MIRROR;
+        }
+        w.write_all(&flags.to_le_bytes())?;
+        w.write_all(&self.size.to_le_bytes())?;
+        w.write_all(&self.cards.0.to_le_bytes())?;
+        w.write_all(&(self.list.len() as u32).to_le_bytes())?;
+        for table in self.list.iter() {
+            table.write_to(&mut w)?;
+        }
+        Ok(())
+    }
+
+    // reconstructs the tables written by [AllTables::write_to]
+    pub fn read_from(mut r: impl Read) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an onitama table file"));
+        }
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported table version {version}")));
+        }
+        let flags = read_u32(&mut r)?;
+        if flags & !(FLAG_LOSSES | FLAG_DTC | FLAG_MIRROR) != 0 {
+            return Err(invalid(format!("unknown flags {flags:#b}")));
+        }
+
+        let size = read_u32(&mut r)?;
+        if !(1..=5).contains(&size) {
+            return Err(invalid(format!("unsupported table size {size}")));
+        }
+        let cards = read_u64(&mut r)?;
+        if cards.count_ones() != 5 || cards >> Card::COUNT != 0 {
+            return Err(invalid(format!("invalid card set {cards:#b}")));
+        }
+        let num_tables = read_u32(&mut r)?;
+        if num_tables as usize != count_indexer(size).total() {
+            return Err(invalid("wrong number of tables"));
+        }
+
+        let mirror = flags & FLAG_MIRROR != 0;
+        if mirror && !Cards(cards).is_symmetric() {
+            return Err(invalid("mirrored tables need a symmetric card set"));
+        }
+
+        let wdl = flags & FLAG_LOSSES != 0;
+        let dtc = flags & FLAG_DTC != 0;
+        // every table is read before the next one is allocated,
+        // so a file that is cut short does not allocate the tables it does not contain
+        let mut tb = Self::new(size, cards, wdl, dtc, |counts| {
+            let mut table = Table::new(counts, wdl, dtc, None)?;
+            if mirror {
+                table.mirror();
+            }
+            table.read_from(&mut r)?;
+            Ok(table)
+        })?;
+        tb.mirrored = mirror;
+        Ok(tb)
+    }
+}
+
+impl Table {
+    pub(super) fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&self.counts.count0.to_le_bytes())?;
+        w.write_all(&self.counts.count1.to_le_bytes())?;
+        w.write_all(&(self.chunk_size as u64).to_le_bytes())?;
+        w.write_all(&(self.list.len() as u64).to_le_bytes())?;
+        write_blocks(w, &self.list)?;
+        write_blocks(w, &self.losses)?;
+        write_dtc(w, &self.dtc)
+    }
+
+    // the table needs to be allocated with the same counts as the stored table
+    pub(super) fn read_from(&self, r: &mut impl Read) -> io::Result<()> {
+        let counts = PawnCount {
+            count0: read_u32(r)?,
+            count1: read_u32(r)?,
+        };
+        let chunk_size = read_u64(r)?;
+        let len = read_u64(r)?;
+        if counts != self.counts
+            || chunk_size != self.chunk_size as u64
+            || len != self.list.len() as u64
+        {
+            return Err(invalid(format!(
+                "expected table {:?}, found {counts:?}",
+                self.counts
+            )));
+        }
+        read_blocks(r, &self.list)?;
+        read_blocks(r, &self.losses)?;
+        read_dtc(r, &self.dtc)
+    }
+}
+
+fn write_blocks(w: &mut impl Write, list: &[AtomicU32]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(BUF_BLOCKS * 4);
+    for chunk in list.chunks(BUF_BLOCKS) {
+        buf.clear();
+        for x in chunk {
+            buf.extend_from_slice(&x.load(Ordering::Relaxed).to_le_bytes());
+        }
+        w.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+fn read_blocks(r: &mut impl Read, list: &[AtomicU32]) -> io::Result<()> {
+    let mut buf = vec![0; BUF_BLOCKS * 4];
+    for chunk in list.chunks(BUF_BLOCKS) {
+        let buf = &mut buf[..chunk.len() * 4];
+        r.read_exact(buf)?;
+        for (x, bytes) in chunk.iter().zip(buf.chunks_exact(4)) {
+            let val = u32::from_le_bytes(bytes.try_into().unwrap());
+            x.store(val, Ordering::Relaxed);
+        }
+    }
+    Ok(())
+}
+
+fn write_dtc(w: &mut impl Write, list: &[AtomicU8]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(BUF_BLOCKS);
+    for chunk in list.chunks(BUF_BLOCKS) {
+        buf.clear();
+        buf.extend(chunk.iter().map(|x| x.load(Ordering::Relaxed)));
+        w.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+fn read_dtc(r: &mut impl Read, list: &[AtomicU8]) -> io::Result<()> {
+    let mut buf = vec![0; BUF_BLOCKS];
+    for chunk in list.chunks(BUF_BLOCKS) {
+        let buf = &mut buf[..chunk.len()];
+        r.read_exact(buf)?;
+        for (x, &byte) in chunk.iter().zip(buf.iter()) {
+            x.store(byte, Ordering::Relaxed);
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(super) fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(super) fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use crate::{
+        index::Indexer,
+        onitama_simd::{count_indexer, BuildConfig},
+    };
+
+    use super::AllTables;
+
+    #[test]
+    fn round_trip() {
+        let tb = BuildConfig::new(2, 0b11111).dtc(true).build();
+        let mut data = vec![];
+        tb.write_to(&mut data).unwrap();
+
+        let loaded = AllTables::read_from(&data[..]).unwrap();
+        assert_eq!(loaded.size(), tb.size());
+        assert_eq!(loaded.cards(), tb.cards());
+        assert_eq!(loaded.count_ones(), 6752579);
+        assert_eq!(loaded.count_losses(), tb.count_losses());
+        assert_eq!(loaded.max_dtc(), tb.max_dtc());
+        for (a, b) in tb.list.iter().zip(loaded.list.iter()) {
+            assert_eq!(a.counts, b.counts);
+            for (a, b) in [(&a.list, &b.list), (&a.losses, &b.losses)] {
+                assert!(a
+                    .iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| a.load(Ordering::Relaxed) == b.load(Ordering::Relaxed)));
+            }
+        }
+
+        data.truncate(data.len() - 1);
+        assert!(AllTables::read_from(&data[..]).is_err());
+    }
+
+    #[test]
+    fn corrupt_header() {
+        let tb = BuildConfig::new(1, 0b11111).build();
+        let mut data = vec![];
+        tb.write_to(&mut data).unwrap();
+
+        // a header for the full game without its tables fails on the first table
+        data[16..20].copy_from_slice(&5u32.to_le_bytes());
+        let num_tables = count_indexer(5).total() as u32;
+        data[28..32].copy_from_slice(&num_tables.to_le_bytes());
+        assert!(AllTables::read_from(&data[..]).is_err());
+    }
+
+    #[test]
+    fn round_trip_mirror() {
+        let tb = BuildConfig::new(2, 0b11111).mirror(true).build();
+        let mut data = vec![];
+        tb.write_to(&mut data).unwrap();
+
+        let loaded = AllTables::read_from(&data[..]).unwrap();
+        assert!(loaded.is_mirrored());
+        assert!(loaded == tb);
+        assert_eq!(loaded.count_ones(), 6752579);
+    }
+}
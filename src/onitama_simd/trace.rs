@@ -0,0 +1,134 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
+
+use super::{AllTables, PawnCount, TableJob, TeamLayout};
+
+// writes what happens in every iteration of the build, see [BuildConfig::trace]
+// every line starts with its kind:
+// `round {count0} {count1} {round}` before the iterations of the jobs of an entry
+// `job {count0} {count1} {iteration} {go_up} {progress} {layouts}` after an iteration of a job
+// `layout {pieces0:07x} {pieces1:07x}` for every layout that was updated by the job
+// `flip {count0} {count1} {plane} {block} {old:08x} {new:08x}` for every block that changed
+// the flips are written after all jobs of the round and again after the pending wins are merged
+// the plane is `wins` or `pending`
+pub(crate) struct Trace {
+    w: Mutex<BufWriter<File>>,
+}
+
+// copy of the wins and the pending wins of the tables that can be changed in a round
+pub(crate) struct Snapshot(Vec<(usize, Vec<u32>, Vec<u32>)>);
+
+impl Trace {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            w: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    pub fn round(&self, counts: PawnCount, round: u32) -> io::Result<()> {
+        let mut w = self.w.lock().unwrap();
+        writeln!(w, "round {} {} {round}", counts.count0, counts.count1)
+    }
+
+    // `go_up` and `layouts` are the values from before the last iteration of the job
+    pub fn job(&self, job: &TableJob, go_up: bool, layouts: &[TeamLayout]) -> io::Result<()> {
+        let mut w = self.w.lock().unwrap();
+        let counts = job.update.current.counts;
+        writeln!(
+            w,
+            "job {} {} {} {go_up} {} {}",
+            counts.count0,
+            counts.count1,
+            job.update.iteration - 1,
+            job.progress,
+            layouts.len()
+        )?;
+        for layout in layouts {
+            writeln!(w, "layout {:07x} {:07x}", layout.pieces0, layout.pieces1)?;
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self, tb: &AllTables, tables: &[usize]) -> Snapshot {
+        let load = |list: &[AtomicU32]| list.iter().map(|x| x.load(Ordering::Relaxed)).collect();
+        let planes = tables.iter().map(|&i| {
+            let table = &tb.list[i];
+            (i, load(&table.list), load(&table.pending))
+        });
+        Snapshot(planes.collect())
+    }
+
+    // writes the blocks that are different from the snapshot
+    pub fn flips(&self, tb: &AllTables, snapshot: &Snapshot) -> io::Result<()> {
+        let mut w = self.w.lock().unwrap();
+        for (i, wins, pending) in &snapshot.0 {
+            let table = &tb.list[*i];
+            let PawnCount { count0, count1 } = table.counts;
+            for (plane, old, new) in [
+                ("wins", wins, &table.list),
+                ("pending", pending, &table.pending),
+            ] {
+                for (block, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                    let new = new.load(Ordering::Relaxed);
+                    if *old != new {
+                        writeln!(
+                            w,
+                            "flip {count0} {count1} {plane} {block} {old:08x} {new:08x}"
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.w.lock().unwrap().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::onitama_simd::{BuildConfig, Threads};
+
+    #[test]
+    fn trace_is_deterministic() {
+        let dir = std::env::temp_dir().join(format!("onitama_trace_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+
+        // tracing always uses a single thread
+        let config = BuildConfig::new(2, 0b11111)
+            .dtc(true)
+            .threads(Threads::Count(4));
+        let a = config.clone().trace(dir.join("a")).build();
+        let b = config.clone().trace(dir.join("b")).build();
+        assert!(a == b);
+
+        let (a, b) = (
+            fs::read(dir.join("a")).unwrap(),
+            fs::read(dir.join("b")).unwrap(),
+        );
+        assert_eq!(a, b);
+        let trace = String::from_utf8(a).unwrap();
+        assert!(trace
+            .lines()
+            .any(|line| line.starts_with("flip 1 1 pending ")));
+        // every job stops making progress once before going up
+        let stops = trace.lines().filter(|line| {
+            let words: Vec<&str> = line.split(' ').collect();
+            words[0] == "job" && words[4] == "false" && words[5] == "false"
+        });
+        assert_eq!(stops.count(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::{iter::repeat, sync::atomic::Ordering};
+use std::{iter::repeat_n, sync::atomic::Ordering};
 
 use bit_iter::BitIter;
 
@@ -7,15 +7,22 @@ use crate::{
     index::{Indexer, InternalIter},
 };
 
-use super::{Accum, Block, ImmutableUpdate, Spread, SubTable, TeamLayout, Update, BLOCK_MASK};
+use super::{
+    Accum, Block, ImmutableUpdate, Spread, SubTable, TeamLayout, Update, BLOCK_MASK, RESOLVED_BIT,
+};
 
 pub struct UpdateStatus {
     pub(crate) progress: bool,
     pub(crate) unresolved: u64,
+    pub(crate) skipped: u64,
+    // a lost state has a distance that does not fit in a byte
+    pub(crate) overflow: bool,
 }
 
 impl Update<'_> {
-    pub fn get_unresolved<const COUNT: bool>(&mut self) -> u64 {
+    // with SKIP, blocks with the RESOLVED_BIT are skipped while searching for new wins
+    // it is a const, so that the default build does not check the bit in the hot loops
+    pub fn get_unresolved<const COUNT: bool, const SKIP: bool>(&mut self) -> u64 {
         let layout = self.layout;
         let mem = &mut *self.mem;
         let ImmutableUpdate {
@@ -32,13 +39,19 @@ impl Update<'_> {
             .indexer(current.counts)
             .for_enumerate(|i, oldk| mem.king_lookup[*oldk] = i as u8);
 
+        // the wins are loaded first, because they tell which blocks are resolved
+        let inv_slice = inv_current.index(layout.invert());
+        mem.wins.resize(layout.indexer(current.counts).total(), 0);
+        self.load_stuff(&inv_slice);
+        let mem = &mut *self.mem;
+
         // every 0 bit means that it could be anything, win loss or draw
         // every 1 bit means that it must be a draw or win
         // we will gradually flip these to 1s, leaving only losses on 0
         // it is initialized to the wins, because those are not lost even when they don't have moves
         mem.status.clear();
         mem.status
-            .extend(repeat(0).take(layout.indexer(current.counts).total()));
+            .extend(repeat_n(0, layout.indexer(current.counts).total()));
 
         for offset in BitIter::from(directions) {
             let mask = mask_lookup[offset];
@@ -54,28 +67,46 @@ impl Update<'_> {
                     take_one,
                     step: (from, to),
                     slice: &mut mem.status,
+                    wins: &mem.wins,
                     mask,
                     king_lookup: &mem.king_lookup,
                 };
-                accum.accumulate();
+                accum.accumulate::<SKIP>();
             }
         }
 
         // we expand, marking all states that are not lost because it has the card
         // then we negate to get only the lost states
-        mem.status
-            .iter_mut()
-            .for_each(|x| *x = !Block(*x).invert().expand().invert().0);
-
-        let inv_slice = inv_current.index(layout.invert());
-        mem.wins.resize(layout.indexer(current.counts).total(), 0);
-        self.load_stuff(&inv_slice);
+        if SKIP {
+            // the losses of resolved blocks were already spread, so they are left out
+            for (x, w) in mem.status.iter_mut().zip(mem.wins.iter()) {
+                *x = if w & RESOLVED_BIT != 0 {
+                    0
+                } else {
+                    !Block(*x).invert().expand().invert().0
+                };
+            }
+        } else {
+            mem.status
+                .iter_mut()
+                .for_each(|x| *x = !Block(*x).invert().expand().invert().0);
+        }
 
-        self.check_unresolved::<COUNT>()
+        self.check_unresolved::<COUNT, SKIP>()
     }
 
     // returns whether there was any progress
-    pub fn update_layout(mut self) -> UpdateStatus {
+    // when going up, the losses of all blocks are needed for the table with one more pawn
+    // so resolved blocks are only skipped before that, see [super::BuildConfig::skip_resolved]
+    pub fn update_layout(self) -> UpdateStatus {
+        if self.immutable.skip_resolved && !self.immutable.go_up {
+            self.update_layout_with::<true>()
+        } else {
+            self.update_layout_with::<false>()
+        }
+    }
+
+    fn update_layout_with<const SKIP: bool>(mut self) -> UpdateStatus {
         let layout = self.layout;
         let ImmutableUpdate {
             current,
@@ -83,11 +114,19 @@ impl Update<'_> {
             go_up,
             mask_lookup,
             directions,
+            dtc,
             ..
         } = *self.immutable;
         let TeamLayout { pieces0, pieces1 } = layout;
 
-        let unresolved = self.get_unresolved::<false>();
+        let unresolved = self.get_unresolved::<false, SKIP>();
+        let overflow = dtc && self.store_loss_dtc();
+        let skipped = if SKIP {
+            let resolved = self.mem.wins.iter().filter(|&w| w & RESOLVED_BIT != 0);
+            resolved.count() as u64
+        } else {
+            0
+        };
         let mem = &mut *self.mem;
 
         let mut progress = false;
@@ -113,58 +152,51 @@ impl Update<'_> {
                     current,
                     leave_one,
                     go_up,
+                    deferred: dtc && !go_up,
                     step: (from, to),
                     slice: &mem.wins,
                     king_lookup: &mem.king_lookup,
                 };
-                progress |= spread.spreadout();
+                progress |= spread.spreadout::<SKIP>();
             }
         }
 
         UpdateStatus {
             progress,
             unresolved,
+            skipped,
+            overflow,
         }
     }
 
-    pub fn check_unresolved<const COUNT: bool>(&mut self) -> u64 {
+    pub fn check_unresolved<const COUNT: bool, const SKIP: bool>(&mut self) -> u64 {
         let layout = self.layout;
         let mem = &mut *self.mem;
-        let current = self.immutable.current;
+        let ImmutableUpdate {
+            inv_current,
+            current,
+            ..
+        } = *self.immutable;
+        let inv_slice = inv_current.index(layout.invert());
 
         let mut all_done = BLOCK_MASK;
         let mut total_unresolved = 0;
-        // let mut num_done = 0;
-        // let mut num_not_done = 0;
-        layout.indexer(current.counts).for_enumerate(|i, _kpos| {
+        layout.indexer(current.counts).for_enumerate(|i, kpos| {
             let w = mem.wins[i];
             let l = mem.status[i];
             mem.status[i] &= !w;
             if COUNT {
                 total_unresolved += 30 - ((w | l) & BLOCK_MASK).count_ones() as u64
+            } else if SKIP && w & RESOLVED_BIT != 0 {
+                // every state of the block is won or lost
             } else {
                 all_done &= w | l;
+                // all 30 states are won or lost, so the block does not change anymore
+                if SKIP && (w | l) & BLOCK_MASK == BLOCK_MASK {
+                    inv_slice[kpos.invert()].fetch_or(RESOLVED_BIT, Ordering::Relaxed);
+                }
             }
-            // if (w | l) & BLOCK_MASK == BLOCK_MASK {
-            //     inv_slice[kpos.invert()].fetch_or(RESOLVED_BIT, Ordering::Relaxed);
-            //     num_done += 1;
-            // } else {
-            //     num_not_done += 1;
-            //     // for mask in mask_iter().take(5) {
-            //     //     let mask = Block(mask).invert().expand().invert().0;
-            //     //     if (w | l) & mask == mask {
-            //     //         self.card_done.fetch_add(1, Ordering::Relaxed);
-            //     //     } else {
-            //     //         self.card_not_done.fetch_add(1, Ordering::Relaxed);
-            //     //     }
-            //     // }
-            // }
         });
-        // if all_done != BLOCK_MASK {
-        //     self.block_done.fetch_add(num_done, Ordering::Relaxed);
-        //     self.block_not_done
-        //         .fetch_add(num_not_done, Ordering::Relaxed);
-        // }
         if COUNT {
             total_unresolved
         } else {
@@ -172,6 +204,56 @@ impl Update<'_> {
         }
     }
 
+    // stores the lost states that are left in `status` by [Update::get_unresolved]
+    // they are stored in the inverted table, just like the wins
+    pub fn store_losses(&mut self) {
+        let layout = self.layout;
+        let mem = &*self.mem;
+        let ImmutableUpdate {
+            inv_current,
+            current,
+            ..
+        } = *self.immutable;
+
+        let inv_slice = inv_current.index_losses(layout.invert());
+        layout.indexer(current.counts).for_enumerate(|i, kpos| {
+            let lost = Block(mem.status[i]).invert().0;
+            inv_slice[kpos.invert()].store(lost, Ordering::Relaxed);
+        });
+    }
+
+    // stores the distance of the states in `status` that are lost for the first time
+    // every state that is lost in this iteration has a move to a win of the previous iteration
+    // returns whether a state is lost for the first time but its distance does not fit
+    fn store_loss_dtc(&mut self) -> bool {
+        let layout = self.layout;
+        let mem = &*self.mem;
+        let ImmutableUpdate {
+            inv_current,
+            current,
+            iteration,
+            ..
+        } = *self.immutable;
+
+        let inv_layout = layout.invert();
+        let dist = u8::try_from(2 * iteration + 2).ok();
+        let mut overflow = false;
+        layout.indexer(current.counts).for_enumerate(|i, kpos| {
+            let lost = Block(mem.status[i]).invert().0;
+            let block = inv_current.block_index(inv_layout, kpos.invert());
+            for bit in BitIter::from(lost) {
+                let dtc = inv_current.dtc(block, bit as u32);
+                if dtc.load(Ordering::Relaxed) == 0 {
+                    match dist {
+                        Some(dist) => dtc.store(dist, Ordering::Relaxed),
+                        None => overflow = true,
+                    }
+                }
+            }
+        });
+        overflow
+    }
+
     fn load_stuff(&mut self, inv_slice: &SubTable) {
         let mem = &mut *self.mem;
 
@@ -181,7 +263,8 @@ impl Update<'_> {
             let s = unsafe { mem.wins.get_mut(i).unwrap_unchecked() };
             let x = unsafe { inv_slice.slice.get(inv_i).unwrap_unchecked() };
             let tmp = x.load(Ordering::Relaxed);
-            *s = Block(tmp).invert().0;
+            // the RESOLVED_BIT is kept, it is not part of the states
+            *s = Block(tmp).invert().0 | tmp & RESOLVED_BIT;
         });
     }
 }
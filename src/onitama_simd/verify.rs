@@ -0,0 +1,190 @@
+use rayon::prelude::*;
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use crate::index::InternalIter;
+
+use super::{AllTables, Move, PawnCount, Position, TeamLayout, Wdl};
+
+// only this many counterexamples are kept, the others are only counted
+const MAX_COUNTEREXAMPLES: usize = 100;
+
+// a state where the tables do not agree with the states after its moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    // marked as won, but no move wins at once or leads to a lost state
+    FalseWin,
+    // not marked as won, but this move wins at once or leads to a lost state
+    MissingWin(Move),
+    // marked as lost, but this move leads to a state that is not won for the opponent
+    FalseLoss(Move),
+    // not marked as won or lost, but every move leads to a win for the opponent
+    MissingLoss,
+    // this move leads to a state that is not part of the tables
+    OutsideTables(Move),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counterexample {
+    pub position: Position,
+    pub violation: Violation,
+}
+
+impl Counterexample {
+    pub fn print(&self) {
+        println!("{:?}", self.violation);
+        print!("{}", self.position.pretty());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Verification {
+    pub checked: u64,
+    pub failures: u64,
+    // the first counterexamples that were found, in no particular order
+    pub counterexamples: Vec<Counterexample>,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        self.failures == 0
+    }
+}
+
+impl AllTables {
+    // checks every stored state against its moves, without using the update code of the build
+    // a state is won iff some move wins at once or leads to a lost state
+    // if the losses are stored, a state is lost iff every move leads to a won state
+    // without stored losses, the lost states are found by probing their moves
+    // this shows that the tables are a fixpoint, not that the won states are the smallest one
+    pub fn verify(&self) -> Verification {
+        let mut layouts: Vec<(PawnCount, TeamLayout)> = vec![];
+        for table in self.list.iter() {
+            let counts = table.counts;
+            layouts.extend(
+                counts
+                    .into_iter()
+                    .filter(|layout| !self.is_mirrored() || layout.is_canonical(counts))
+                    .map(|layout| (counts, layout)),
+            );
+        }
+
+        let checked = AtomicU64::new(0);
+        let failures = AtomicU64::new(0);
+        let counterexamples = Mutex::new(vec![]);
+
+        let iter = layouts.par_iter();
+
+        iter.for_each(|&(counts, layout)| {
+            layout.indexer(counts).for_each(|kpos| {
+                for bit in 0..30 {
+                    let pos = self.position(layout, *kpos, bit);
+                    if pos.winner().is_some() {
+                        continue;
+                    }
+                    checked.fetch_add(1, Ordering::Relaxed);
+                    let Some(violation) = self.check_state(&pos) else {
+                        continue;
+                    };
+                    failures.fetch_add(1, Ordering::Relaxed);
+                    let mut list = counterexamples.lock().unwrap();
+                    if list.len() < MAX_COUNTEREXAMPLES {
+                        list.push(Counterexample {
+                            position: pos,
+                            violation,
+                        });
+                    }
+                }
+            })
+        });
+
+        Verification {
+            checked: checked.into_inner(),
+            failures: failures.into_inner(),
+            counterexamples: counterexamples.into_inner().unwrap(),
+        }
+    }
+
+    fn check_state(&self, pos: &Position) -> Option<Violation> {
+        let mut winning = None;
+        let mut escape = None;
+        for mv in pos.moves() {
+            let next = pos.play(mv);
+            // a move can only win the game for the player that makes it
+            if next.winner().is_some() {
+                winning = Some(mv);
+                continue;
+            }
+            match self.probe_wdl(&next) {
+                None => return Some(Violation::OutsideTables(mv)),
+                Some(Wdl::Loss) => winning = Some(mv),
+                Some(Wdl::Draw) => escape = Some(mv),
+                Some(Wdl::Win) => {}
+            }
+        }
+
+        let win = self.probe(pos)?;
+        match (win, winning) {
+            (true, None) => return Some(Violation::FalseWin),
+            (false, Some(mv)) => return Some(Violation::MissingWin(mv)),
+            _ => {}
+        }
+        if !self.wdl || win {
+            return None;
+        }
+        let loss = self.probe_wdl(pos)? == Wdl::Loss;
+        match (loss, escape) {
+            (true, Some(mv)) => Some(Violation::FalseLoss(mv)),
+            (false, None) => Some(Violation::MissingLoss),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use crate::onitama_simd::{AllTables, BuildConfig, PawnCount};
+
+    use super::Violation;
+
+    #[test]
+    fn verify_wdl() {
+        let tb = BuildConfig::new(2, 0b11111).wdl(true).build();
+        let result = tb.verify();
+        assert!(result.is_ok(), "{:?}", result.counterexamples.first());
+        assert!(result.checked > tb.count_ones());
+    }
+
+    #[test]
+    fn verify_finds_corruption() {
+        let tb = AllTables::build(1, 0b11111);
+        assert!(tb.verify().is_ok());
+
+        // flip a state that is won, so it looks like it has no winning move
+        let counts = PawnCount::default();
+        let table = tb.index_count(counts);
+        let mut won = None;
+        tb.for_each_state(counts, |pos, layout, kpos, bit| {
+            if won.is_none() && pos.winner().is_none() && tb.probe(&pos) == Some(true) {
+                won = Some((layout, kpos, bit));
+            }
+        });
+        let (layout, kpos, bit) = won.unwrap();
+        table.index(layout)[kpos].fetch_xor(1 << bit, Ordering::Relaxed);
+
+        let result = tb.verify();
+        assert!(!result.is_ok());
+        assert!(result
+            .counterexamples
+            .iter()
+            .any(|c| matches!(c.violation, Violation::MissingWin(_))));
+        for c in &result.counterexamples {
+            c.print();
+        }
+    }
+}